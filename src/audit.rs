@@ -0,0 +1,66 @@
+//! Checks that a table's conclusions agree with one another, whoever produced them: a state is
+//! losing if any child is winning, winning if every child is losing, and a state without
+//! children is concluded by the game's rules, such as the convention in Nim.
+
+use std::fmt;
+
+use crate::game::Game;
+use crate::solution::Conclusion;
+use crate::state::State;
+
+/// A state whose stored conclusion differs from the one its children imply.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Violation<P = State> {
+    pub state: P,
+    pub stored: Conclusion,
+    pub implied: Conclusion,
+    /// The child that decides `implied`: a winning child when it is losing, or an unsolved
+    /// child when it is unknown. A state is only winning when every child is losing, so no
+    /// single child proves that.
+    pub witness: Option<P>,
+    /// Whether the game is over in `state`, so that its rules decide `implied`.
+    pub game_over: bool,
+}
+
+/// Every violation in `game`, in order of number, where `conclusion` looks up the stored
+/// conclusion of each state by number.
+pub(crate) fn audit<G, F>(game: &G, conclusion: F) -> Vec<Violation<G::Position>>
+    where G: Game, F: Fn(usize) -> Conclusion {
+    let mut violations = Vec::new();
+    for i in 0..game.len() {
+        let children = game.successors(i);
+        let (implied, witness) = if children.is_empty() {
+            (game.terminal(i), None)
+        } else if let Some(&c) = children.iter().find(|&&c| conclusion(c) == Conclusion::Winning) {
+            (Conclusion::Losing, Some(c))
+        } else if let Some(&c) = children.iter().find(|&&c| conclusion(c) == Conclusion::Unknown) {
+            (Conclusion::Unknown, Some(c))
+        } else {
+            (Conclusion::Winning, None)
+        };
+
+        let stored = conclusion(i);
+        if stored != implied {
+            violations.push(Violation {
+                state: game.position(i),
+                stored,
+                implied,
+                witness: witness.map(|c| game.position(c)),
+                game_over: children.is_empty(),
+            });
+        }
+    }
+    violations
+}
+
+impl<P: fmt::Debug> fmt::Display for Violation<P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{:?}: stored as {:?} but its children imply {:?}", self.state, self.stored, self.implied)?;
+        match (&self.witness, self.implied) {
+            (Some(child), Conclusion::Losing) => write!(f, ", since {:?} is winning", child),
+            (Some(child), _) => write!(f, ", since {:?} is unsolved", child),
+            (None, _) if self.game_over => write!(f, ", as the game is over"),
+            (None, _) => write!(f, ", since every child is losing"),
+        }
+    }
+}
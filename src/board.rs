@@ -0,0 +1,322 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::solution::QueryError;
+use crate::state::{Move, MoveError, State};
+use crate::subtraction::TakeSet;
+
+/// The largest size of each heap, e.g. `[1, 3, 5, 7]` for Marienbad.
+///
+/// Each state on the board is numbered by reading its heaps as the digits of a mixed-radix
+/// number, so states can be stored densely and numbered in lexicographic order.
+///
+/// A symmetric board instead treats heaps with the same limit as interchangeable: only states
+/// whose interchangeable heaps are in ascending order (the canonical states) are numbered, and
+/// every other state shares the number of its canonical permutation.
+///
+/// A board may also restrict how many sticks a move takes from each heap, for subtraction
+/// games. Heaps are then only interchangeable if their take-sets are the same too.
+#[derive(Clone, Eq, PartialEq)]
+pub struct Board {
+    limits: Vec<u8>,
+    symmetric: bool,
+    take_sets: Option<Vec<TakeSet>>,
+    // Each group of interchangeable heaps is one digit of a state's number, with the last group
+    // counting fastest. Without symmetry every heap is a group of its own.
+    groups: Vec<HeapGroup>,
+}
+
+/// Why a board can't be built.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BoardError {
+    /// The board has more states than can be numbered and stored.
+    TooLarge,
+    /// The number of take-sets doesn't match the number of heaps.
+    TakeSetCount { heaps: usize, sets: usize },
+}
+
+#[derive(Clone, Eq, PartialEq)]
+struct HeapGroup {
+    heaps: Vec<usize>,
+    limit: u8,
+    // Number of ascending arrangements of the group's heaps, i.e. the radix of its digit
+    len: usize,
+    // Place value of the group's digit
+    stride: usize,
+}
+
+impl Board {
+    pub fn new(limits: &[u8]) -> Result<Self, BoardError> {
+        Self::with_rules(limits, false, None)
+    }
+
+    /// A board that only distinguishes states up to permutations of heaps with equal limits.
+    pub fn symmetric(limits: &[u8]) -> Result<Self, BoardError> {
+        Self::with_rules(limits, true, None)
+    }
+
+    /// The same board, except that a move may only take a number of sticks from heap `i` that
+    /// is in `take_sets[i]`, so there must be one set per heap. Splitting groups of equal heaps
+    /// apart can make the board too large.
+    pub fn with_take_sets(&self, take_sets: Vec<TakeSet>) -> Result<Self, BoardError> {
+        if take_sets.len() != self.limits.len() {
+            return Err(BoardError::TakeSetCount { heaps: self.limits.len(), sets: take_sets.len() });
+        }
+        Self::with_rules(&self.limits, self.symmetric, Some(take_sets))
+    }
+
+    fn with_rules(limits: &[u8], symmetric: bool, take_sets: Option<Vec<TakeSet>>) -> Result<Self, BoardError> {
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        for heap in 0..limits.len() {
+            let same_rules = |other: usize| {
+                limits[other] == limits[heap]
+                    && take_sets.as_ref().is_none_or(|sets| sets[other] == sets[heap])
+            };
+            match groups.iter_mut().find(|g| symmetric && same_rules(g[0])) {
+                Some(group) => group.push(heap),
+                None => groups.push(vec![heap]),
+            }
+        }
+
+        let mut groups: Vec<HeapGroup> = groups.into_iter().map(|heaps| {
+            let limit = limits[heaps[0]];
+            let len = binomial(limit as usize + heaps.len(), heaps.len()).ok_or(BoardError::TooLarge)?;
+            Ok(HeapGroup { heaps, limit, len, stride: 1 })
+        }).collect::<Result<_, _>>()?;
+        for g in (1..groups.len()).rev() {
+            groups[g - 1].stride = groups[g].stride.checked_mul(groups[g].len).ok_or(BoardError::TooLarge)?;
+        }
+        // No table can hold more than isize::MAX states, even at a byte each
+        let len = groups.first().map_or(Some(1), |g| g.stride.checked_mul(g.len));
+        if len.is_none_or(|len| len > isize::MAX as usize) {
+            return Err(BoardError::TooLarge);
+        }
+        Ok(Self { limits: limits.to_vec(), symmetric, take_sets, groups })
+    }
+
+    pub fn limits(&self) -> &[u8] {
+        &self.limits
+    }
+
+    pub fn is_symmetric(&self) -> bool {
+        self.symmetric
+    }
+
+    /// The take-set of each heap, unless any number of sticks may be taken.
+    pub fn take_sets(&self) -> Option<&[TakeSet]> {
+        self.take_sets.as_deref()
+    }
+
+    /// Whether the rules allow taking `mv.take` sticks from `mv.heap`, if it has that many.
+    pub fn allows(&self, mv: Move) -> bool {
+        // Heaps the board doesn't have are left for `State::apply` to reject
+        self.take_sets.as_ref().is_none_or(|sets| sets.get(mv.heap).is_none_or(|set| set.allows(mv.take)))
+    }
+
+    /// The nim-sum of the Grundy values of each heap of `s` alone, which by the Sprague-Grundy
+    /// theorem is the Grundy value of `s`. Without take-sets it is just the nim-sum of `s`.
+    pub fn grundy_sum(&self, s: &State) -> Result<u8, QueryError> {
+        self.try_index(s)?;
+        Ok((0..self.limits.len()).fold(0, |acc, heap| acc ^ self.heap_grundy(heap)[s.0[heap] as usize]))
+    }
+
+    /// Every move the rules allow from `s`.
+    pub fn legal_moves(&self, s: &State) -> Vec<Move> {
+        s.legal_moves().into_iter().filter(|&mv| self.allows(mv)).collect()
+    }
+
+    /// Play `mv` from `s`, as long as the rules allow it.
+    pub fn apply(&self, s: &State, mv: Move) -> Result<State, MoveError> {
+        let child = s.apply(mv)?;
+        if !self.allows(mv) {
+            return Err(MoveError::NotInTakeSet { heap: mv.heap, take: mv.take });
+        }
+        Ok(child)
+    }
+
+    /// The Grundy value of heap `heap` alone, at each size up to its limit.
+    pub fn heap_grundy(&self, heap: usize) -> Vec<u8> {
+        let len = self.limits[heap] as usize + 1;
+        match &self.take_sets {
+            Some(sets) => sets[heap].grundy_sequence(len),
+            None => (0..len).map(|size| size as u8).collect(),
+        }
+    }
+
+    pub fn contains(&self, s: &State) -> bool {
+        s.0.len() == self.limits.len() && s.0.iter().zip(&self.limits).all(|(h, l)| h <= l)
+    }
+
+    /// Number of states on the board.
+    pub fn len(&self) -> usize {
+        self.groups.iter().map(|g| g.len).product()
+    }
+
+    /// A board always has at least its empty state.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// The number of `s`, or why it isn't on the board.
+    pub fn try_index(&self, s: &State) -> Result<usize, QueryError> {
+        if s.0.len() != self.limits.len() {
+            return Err(QueryError::HeapCount { expected: self.limits.len(), found: s.0.len() });
+        }
+        for (heap, (&sticks, &limit)) in s.0.iter().zip(&self.limits).enumerate() {
+            if sticks > limit {
+                return Err(QueryError::OutOfRange { heap, sticks, limit });
+            }
+        }
+        Ok(self.index(s))
+    }
+
+    /// The number of `s`, which must be on the board.
+    pub(crate) fn index(&self, s: &State) -> usize {
+        debug_assert!(self.contains(s));
+        self.groups.iter().map(|g| {
+            let mut heaps: Vec<u8> = g.heaps.iter().map(|&h| s.0[h]).collect();
+            heaps.sort_unstable();
+            // Rank the ascending heaps in the combinatorial number system
+            let rank: usize = heaps.iter().enumerate()
+                .map(|(j, &h)| choose(h as usize + j, j + 1))
+                .sum();
+            rank * g.stride
+        }).sum()
+    }
+
+    /// The canonical state numbered `index`, which must be less than `len`.
+    pub(crate) fn state(&self, index: usize) -> State {
+        let mut s = self.empty_state();
+        for g in &self.groups {
+            let mut rank = index / g.stride % g.len;
+            for j in (0..g.heaps.len()).rev() {
+                let mut h = g.limit as usize;
+                while choose(h + j, j + 1) > rank {
+                    h -= 1;
+                }
+                rank -= choose(h + j, j + 1);
+                s.0[g.heaps[j]] = h as u8;
+            }
+        }
+        s
+    }
+
+    /// The state with the same number as `s` that the board actually stores.
+    pub fn canonical(&self, s: &State) -> Result<State, QueryError> {
+        Ok(self.state(self.try_index(s)?))
+    }
+
+    /// `s` with its sticks sorted, smallest first, among heaps that share a take-set. Limits
+    /// don't change which moves are allowed, so this never changes who wins, but the result may
+    /// not be on the board.
+    pub fn sorted(&self, s: &State) -> State {
+        let rules = |heap: usize| self.take_sets.as_ref().map(|sets| sets.get(heap));
+        let mut sorted = s.clone();
+        let mut done = vec![false; s.0.len()];
+        for heap in 0..s.0.len() {
+            if done[heap] {
+                continue;
+            }
+            let alike: Vec<usize> = (heap..s.0.len()).filter(|&other| rules(other) == rules(heap)).collect();
+            let mut sticks: Vec<u8> = alike.iter().map(|&h| s.0[h]).collect();
+            sticks.sort_unstable();
+            for (&h, &n) in alike.iter().zip(&sticks) {
+                sorted.0[h] = n;
+                done[h] = true;
+            }
+        }
+        sorted
+    }
+
+    pub fn empty_state(&self) -> State {
+        State(vec![0; self.limits.len()])
+    }
+
+    pub fn full_state(&self) -> State {
+        State(self.limits.clone())
+    }
+
+    /// Every canonical state on the board, in order of their numbers. Without symmetry that is
+    /// every state, in lexicographic order.
+    pub fn states(&self) -> impl Iterator<Item=State> + '_ {
+        (0..self.len()).map(move |i| self.state(i))
+    }
+
+    /// Indices of every state reachable from `parent` in one move, each listed once.
+    pub(crate) fn children(&self, parent: usize) -> Vec<usize> {
+        let s = self.state(parent);
+        let mut children: Vec<usize> = self.legal_moves(&s)
+            .into_iter()
+            .map(|mv| self.index(&s.apply(mv).unwrap()))
+            .collect();
+        children.sort_unstable();
+        children.dedup();
+        children
+    }
+
+    /// Indices of every state from which `child` can be reached in one move, each listed once.
+    pub(crate) fn parents(&self, child: usize) -> Vec<usize> {
+        let s = self.state(child);
+        let mut parents = Vec::new();
+        for (heap, &limit) in self.limits.iter().enumerate() {
+            for size in s.0[heap] + 1..=limit {
+                if !self.allows(Move { heap, take: size - s.0[heap] }) {
+                    continue;
+                }
+                let mut parent = s.clone();
+                parent.0[heap] = size;
+                parents.push(self.index(&parent));
+            }
+        }
+        parents.sort_unstable();
+        parents.dedup();
+        parents
+    }
+}
+
+impl FromStr for Board {
+    type Err = String;
+
+    /// Parses comma-separated heap limits, e.g. "3,4,5".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let limits = s.split(',')
+            .map(|l| l.trim().parse())
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|e| e.to_string())?;
+        Self::new(&limits).map_err(|e| e.to_string())
+    }
+}
+
+impl fmt::Display for BoardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            BoardError::TooLarge => write!(f, "the board has too many states to store"),
+            BoardError::TakeSetCount { heaps, sets } => write!(f, "{} take-sets for {} heaps", sets, heaps),
+        }
+    }
+}
+
+impl Error for BoardError {}
+
+/// n choose k, or `None` if it doesn't fit in a `usize`.
+fn binomial(n: usize, k: usize) -> Option<usize> {
+    if k > n {
+        return Some(0);
+    }
+    // Going no further than halfway, each step's count is at least the last one's, so stop as
+    // soon as one outgrows a `usize`. Until then each product fits in a `u128`.
+    let mut c: u128 = 1;
+    for i in 0..k.min(n - k) {
+        c = c * (n - i) as u128 / (i + 1) as u128;
+        if c > usize::MAX as u128 {
+            return None;
+        }
+    }
+    Some(c as usize)
+}
+
+// A binomial no larger than some group's count, which fit when the board was built
+fn choose(n: usize, k: usize) -> usize {
+    binomial(n, k).expect("no larger than the group's count")
+}
@@ -0,0 +1,265 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::PathBuf;
+
+use nim::dot::DotOptions;
+use nim::export::Format;
+use nim::packed::{self, PackedTable};
+use nim::tablebase::{self, TablebaseError};
+use nim::{Board, Convention, OctalCode, SolutionMap, State, TakeSet};
+
+pub const USAGE: &str = "\
+Usage: nim [COMMAND] [OPTIONS]
+
+Commands:
+  solve              print who wins from every state (the default)
+  query STATE        print who wins from one state, e.g. `query 1,2,3,0`
+  explain STATE      print the proof of who wins from one state
+  play               play a game against the solver
+  export             write the table in a machine-readable format
+  grundy             print the Grundy values of each heap alone and their period
+  verify             check the table against Bouton's theorem, itself and a search
+  crosscheck         compare the solver with a search on many small boards
+  help               show this message
+
+Options:
+  --heaps LIMITS     largest size of each heap [default: 1,3,5,7]
+  --convention C     `misere` (making the last move loses) or `normal` [default: misere]
+  --symmetric        solve one state out of each permutation of equal heaps
+  --take SETS        only allow taking these numbers of sticks, e.g. `1,2,3`, or one set
+                     per heap separated by `/`, e.g. `1,2/1,3,4/1,2/1`
+  --octal CODE       play the octal game CODE instead, where heaps may split, e.g. `0.77`,
+                     or `kayles`, `dawson` or `lasker` (Lasker's Nim, 4.3...) by name;
+                     works with solve, query, grundy and verify
+  --sticks N         octal: most sticks in a position [default: 12]
+  --table FILE       load the table from a tablebase file instead of solving it; a packed
+                     file only works with query and verify
+  --output FILE      write to FILE instead of standard output
+  --format F         export format: `json`, `csv`, `dot`, `text`, `tablebase` or
+                     `packed` [default: json]
+  --from STATE       dot: only draw states reachable from STATE
+  --optimal          dot: only draw moves into winning states
+  --collapse         dot: draw permutations of a state as one node
+  --depth N          explain: levels of moves to show [default: 2]";
+
+// The most sticks an octal game's table may hold. Positions are multisets of heaps, and there
+// are over 200,000 of them with up to 40 sticks.
+const MAX_OCTAL_STICKS: u8 = 40;
+
+pub enum Command {
+    Solve,
+    Query(State),
+    Explain { state: State, depth: usize },
+    Play,
+    Export(Format),
+    Grundy,
+    Verify,
+    CrossCheck,
+    Help,
+}
+
+/// A table to answer commands from.
+pub enum Table {
+    Solved(SolutionMap),
+    /// A packed file given with `--table`, which only knows who wins.
+    Packed(PackedTable),
+}
+
+pub struct Args {
+    pub command: Command,
+    /// The board and convention given on the command line. When loading a table, these are
+    /// only set if given explicitly, and the table must then agree with them.
+    pub board: Option<Board>,
+    pub convention: Option<Convention>,
+    /// The octal game to play instead of Nim, and the most sticks in its positions.
+    pub octal: Option<OctalCode>,
+    pub sticks: u8,
+    pub table: Option<PathBuf>,
+    pub output: Option<PathBuf>,
+    pub dot: DotOptions,
+}
+
+pub fn parse<I: IntoIterator<Item=String>>(args: I) -> Result<Args, String> {
+    let mut args = args.into_iter();
+    let mut command = None;
+    let mut state = None;
+    let mut depth = 2;
+    let mut limits = None;
+    let mut symmetric = false;
+    let mut take_sets = None;
+    let mut convention = None;
+    let mut octal = None;
+    let mut sticks = 12;
+    let mut table = None;
+    let mut format = Format::Json;
+    let mut output = None;
+    let mut dot = DotOptions::default();
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{} needs a value", name));
+        match arg.as_str() {
+            "--heaps" => {
+                let heaps = value("--heaps")?;
+                limits = Some(heaps.split(',')
+                    .map(|l| l.trim().parse())
+                    .collect::<Result<Vec<u8>, _>>()
+                    .map_err(|e| format!("invalid heap limits {:?}: {}", heaps, e))?);
+            },
+            "--convention" => convention = Some(value("--convention")?.parse()?),
+            "--symmetric" => symmetric = true,
+            "--take" => {
+                let sets = value("--take")?;
+                take_sets = Some(sets.split('/')
+                    .map(|set| set.parse::<TakeSet>().map_err(|e| format!("invalid take-set {:?}: {}", set, e)))
+                    .collect::<Result<Vec<_>, _>>()?);
+            },
+            "--octal" => {
+                let code = value("--octal")?;
+                octal = Some(code.parse::<OctalCode>().map_err(|e| format!("invalid octal code {:?}: {}", code, e))?);
+            },
+            "--sticks" => {
+                let n = value("--sticks")?;
+                sticks = n.parse().map_err(|e| format!("invalid number of sticks {:?}: {}", n, e))?;
+            },
+            "--table" => table = Some(PathBuf::from(value("--table")?)),
+            "--output" => output = Some(PathBuf::from(value("--output")?)),
+            "--format" => format = value("--format")?.parse()?,
+            "--from" => dot.start = Some(parse_state(&value("--from")?)?),
+            "--optimal" => dot.optimal_only = true,
+            "--collapse" => dot.collapse = true,
+            "--depth" => {
+                let n = value("--depth")?;
+                depth = n.parse().map_err(|e| format!("invalid depth {:?}: {}", n, e))?;
+            },
+            _ if arg.starts_with("--") => return Err(format!("unknown option {:?}", arg)),
+            _ if command.is_none() => command = Some(arg),
+            _ if matches!(command.as_deref(), Some("query") | Some("explain")) && state.is_none() => {
+                state = Some(parse_state(&arg)?);
+            },
+            _ => return Err(format!("unexpected argument {:?}", arg)),
+        }
+    }
+
+    let command = match command.as_deref().unwrap_or("solve") {
+        "solve" => Command::Solve,
+        "query" => Command::Query(state.ok_or("query needs a state, e.g. `query 1,2,3,0`")?),
+        "explain" => Command::Explain {
+            state: state.ok_or("explain needs a state, e.g. `explain 1,2,3,0`")?,
+            depth,
+        },
+        "play" => Command::Play,
+        "export" => Command::Export(format),
+        "grundy" => Command::Grundy,
+        "verify" => Command::Verify,
+        "crosscheck" => Command::CrossCheck,
+        "help" => Command::Help,
+        other => return Err(format!("unknown command {:?}", other)),
+    };
+    if octal.is_some() {
+        return parse_octal(command, octal, sticks, convention, output, limits.is_some() || symmetric
+            || take_sets.is_some() || table.is_some());
+    }
+    let mut args = Args { command, board: None, convention, octal, sticks, table, output, dot };
+    if args.table.is_none() || limits.is_some() || symmetric || take_sets.is_some() {
+        let limits = limits.unwrap_or_else(|| vec![1, 3, 5, 7]);
+        let mut board = if symmetric { Board::symmetric(&limits) } else { Board::new(&limits) }
+            .map_err(|e| format!("heap limits {:?}: {}", limits, e))?;
+        if let Some(mut sets) = take_sets {
+            if sets.len() == 1 {
+                sets = vec![sets[0].clone(); limits.len()];
+            }
+            if sets.len() != limits.len() {
+                return Err(format!("--take gives {} take-sets for {} heaps", sets.len(), limits.len()));
+            }
+            board = board.with_take_sets(sets).map_err(|e| format!("invalid take-sets: {}", e))?;
+        }
+        if args.table.is_none() {
+            args.check_states(&board)?;
+            args.convention = args.convention.or(Some(Convention::Misere));
+        }
+        args.board = Some(board);
+    }
+    Ok(args)
+}
+
+fn parse_octal(command: Command, octal: Option<OctalCode>, sticks: u8, convention: Option<Convention>,
+               output: Option<PathBuf>, board_options: bool) -> Result<Args, String> {
+    if board_options {
+        return Err("--heaps, --symmetric, --take and --table only apply to Nim, not --octal".to_string());
+    }
+    match command {
+        Command::Solve | Command::Query(_) | Command::Verify if sticks > MAX_OCTAL_STICKS => {
+            return Err(format!("--sticks {} makes too many positions to solve; at most {} will do",
+                               sticks, MAX_OCTAL_STICKS));
+        },
+        Command::Solve | Command::Query(_) | Command::Grundy | Command::Verify | Command::Help => {},
+        _ => return Err("--octal only works with solve, query, grundy and verify".to_string()),
+    }
+    let convention = convention.or(Some(Convention::Misere));
+    Ok(Args { command, board: None, convention, octal, sticks, table: None, output, dot: DotOptions::default() })
+}
+
+impl Args {
+    /// Load the table named by `--table`, which may be a tablebase or packed file, or solve
+    /// the board given on the command line.
+    pub fn table(&self) -> Result<Table, Box<dyn Error>> {
+        let path = match &self.table {
+            Some(path) => path,
+            None => return Ok(Table::Solved(SolutionMap::solved(self.board.as_ref().unwrap(), self.convention.unwrap()))),
+        };
+        let mut bytes = Vec::new();
+        BufReader::new(File::open(path)?).read_to_end(&mut bytes)?;
+        let table = if bytes.starts_with(packed::MAGIC) {
+            Table::Packed(PackedTable::from_bytes(bytes)?)
+        } else {
+            Table::Solved(tablebase::read(bytes.as_slice())?)
+        };
+        let (board, convention) = match &table {
+            Table::Solved(sols) => (sols.board(), sols.convention()),
+            Table::Packed(packed) => (packed.board(), packed.convention()),
+        };
+        if let Some(given) = &self.board {
+            if given != board {
+                return Err(TablebaseError::Mismatch(format!(
+                    "the file holds {} rather than {}", describe(board), describe(given))).into());
+            }
+        }
+        if let Some(given) = self.convention {
+            if given != convention {
+                return Err(TablebaseError::Mismatch(format!(
+                    "the file holds {:?} play rather than {:?}", convention, given)).into());
+            }
+        }
+        self.check_states(board)?;
+        Ok(table)
+    }
+
+    /// Make sure any states given on the command line are on the board.
+    fn check_states(&self, board: &Board) -> Result<(), String> {
+        let given = match &self.command {
+            Command::Query(s) | Command::Explain { state: s, .. } => Some(s),
+            _ => self.dot.start.as_ref(),
+        };
+        match given {
+            Some(s) if !board.contains(s) => Err(format!("{:?} is not on the board", s)),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn describe(board: &Board) -> String {
+    let mut text = format!("heap limits {:?}", board.limits());
+    if board.is_symmetric() {
+        text += " (symmetric)";
+    }
+    if let Some(sets) = board.take_sets() {
+        let sets: Vec<String> = sets.iter().map(TakeSet::to_string).collect();
+        text += &format!(" taking {}", sets.join("/"));
+    }
+    text
+}
+
+fn parse_state(s: &str) -> Result<State, String> {
+    s.parse().map_err(|e| format!("invalid state {:?}: {}", s, e))
+}
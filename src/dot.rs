@@ -0,0 +1,91 @@
+//! Graphviz rendering of the game graph: states are nodes, coloured by conclusion, and moves
+//! are edges.
+
+use std::collections::{HashSet, VecDeque};
+use std::io::{self, Write};
+
+use crate::{Conclusion, Move, QueryError, SolutionMap, State};
+
+#[derive(Default)]
+pub struct DotOptions {
+    /// Only draw states reachable from here, instead of the whole board.
+    pub start: Option<State>,
+    /// Only draw moves into winning states, where there are any.
+    pub optimal_only: bool,
+    /// Draw states that are permutations of one another as a single node, where the heaps
+    /// permuted share a take-set.
+    pub collapse: bool,
+}
+
+pub fn write<W: Write>(sols: &SolutionMap, options: &DotOptions, mut out: W) -> io::Result<()> {
+    let states: Vec<State> = match &options.start {
+        Some(start) => reachable(sols, options, start)?,
+        None => sols.board().states().collect(),
+    };
+    let node = |s: &State| if options.collapse { Ok(sols.board().sorted(s)) } else { sols.board().canonical(s) };
+
+    writeln!(out, "digraph nim {{")?;
+    writeln!(out, "    node [style=filled];")?;
+    let mut drawn = HashSet::new();
+    for s in &states {
+        if drawn.insert(node(s)?) {
+            writeln!(out, "    \"{}\" [fillcolor={}];", id(&node(s)?), colour(sols.conclusion(s)?))?;
+        }
+    }
+    let mut joined = HashSet::new();
+    for s in &states {
+        for mv in moves(sols, options, s)? {
+            let (from, to) = (node(s)?, node(&s.apply(mv).unwrap())?);
+            // Collapsed moves only keep the number taken, since the heap is ambiguous
+            let label = if options.collapse { format!("take {}", mv.take) } else { mv.to_string() };
+            if joined.insert((from.clone(), to.clone(), label.clone())) {
+                writeln!(out, "    \"{}\" -> \"{}\" [label=\"{}\"];", id(&from), id(&to), label)?;
+            }
+        }
+    }
+    writeln!(out, "}}")
+}
+
+fn moves(sols: &SolutionMap, options: &DotOptions, s: &State) -> Result<Vec<Move>, QueryError> {
+    if options.optimal_only {
+        // When every move loses, every move is as good as any other
+        let winning = sols.winning_moves(s)?;
+        if !winning.is_empty() {
+            return Ok(winning);
+        }
+    }
+    Ok(sols.board().legal_moves(s))
+}
+
+fn reachable(sols: &SolutionMap, options: &DotOptions, start: &State) -> Result<Vec<State>, QueryError> {
+    let mut seen = vec![false; sols.board().len()];
+    let mut order = Vec::new();
+    let mut queue = VecDeque::new();
+    seen[sols.board().try_index(start)?] = true;
+    queue.push_back(start.clone());
+    while let Some(s) = queue.pop_front() {
+        for mv in moves(sols, options, &s)? {
+            let child = s.apply(mv).unwrap();
+            let i = sols.board().index(&child);
+            if !seen[i] {
+                seen[i] = true;
+                queue.push_back(child);
+            }
+        }
+        order.push(s);
+    }
+    Ok(order)
+}
+
+fn id(s: &State) -> String {
+    let heaps: Vec<String> = s.0.iter().map(|h| h.to_string()).collect();
+    heaps.join(",")
+}
+
+fn colour(v: Conclusion) -> &'static str {
+    match v {
+        Conclusion::Winning => "palegreen",
+        Conclusion::Losing => "lightcoral",
+        Conclusion::Unknown => "lightgrey",
+    }
+}
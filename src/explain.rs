@@ -0,0 +1,46 @@
+//! Proof trees showing why a state has its conclusion: a losing state by the one move that
+//! leaves a winning state, and a winning state by every move, each leaving a losing state.
+
+use std::io::{self, Write};
+
+use crate::solution::{Justification, SolutionMap};
+use crate::state::State;
+
+/// Write the proof tree for `s`, following moves up to `depth` levels deep.
+pub fn explain<W: Write>(sols: &SolutionMap, s: &State, depth: usize, mut out: W) -> io::Result<()> {
+    write!(out, "{:?}: ", s)?;
+    node(sols, s, depth, 1, &mut out)
+}
+
+// Finish the line for `s`, whose move and state are already written, then write its subtree
+fn node<W: Write>(sols: &SolutionMap, s: &State, depth: usize, indent: usize, out: &mut W) -> io::Result<()> {
+    let conclusion = sols.conclusion(s)?;
+    let justification = sols.justification(s)?;
+    let moves = match justification {
+        Justification::GameOver => {
+            writeln!(out, "{:?}, as the game is over", conclusion)?;
+            vec![]
+        },
+        Justification::WinningMove(mv) => {
+            writeln!(out, "{:?}, as {} leaves a winning state", conclusion, mv)?;
+            vec![mv]
+        },
+        Justification::EveryMoveLoses => {
+            writeln!(out, "{:?}, as every move leaves a losing state", conclusion)?;
+            sols.board().legal_moves(s)
+        },
+        Justification::Unproven => {
+            writeln!(out, "{:?}, though no move leaves a winning state", conclusion)?;
+            vec![]
+        },
+    };
+    if depth == 0 {
+        return Ok(());
+    }
+    for mv in moves {
+        let child = s.apply(mv).unwrap();
+        write!(out, "{}{} -> {:?}: ", "  ".repeat(indent), mv, child)?;
+        node(sols, &child, depth - 1, indent + 1, out)?;
+    }
+    Ok(())
+}
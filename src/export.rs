@@ -0,0 +1,95 @@
+//! Machine-readable dumps of a solved table, one record per state. Heaps are numbered from 1
+//! in winning moves, just as they are shown to players.
+
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use crate::dot::{self, DotOptions};
+use crate::packed::PackedTable;
+use crate::tablebase;
+use crate::{Move, SolutionMap};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Format {
+    /// The human-readable listing from `SolutionMap`'s `Display`.
+    Text,
+    Json,
+    Csv,
+    /// The game graph, drawn by the `dot` module.
+    Dot,
+    /// The binary file written by the `tablebase` module.
+    Tablebase,
+    /// The one bit per state file written by the `packed` module.
+    Packed,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            "dot" => Ok(Format::Dot),
+            "tablebase" => Ok(Format::Tablebase),
+            "packed" => Ok(Format::Packed),
+            _ => Err(format!("unknown output format {:?}", s)),
+        }
+    }
+}
+
+pub fn write<W: Write>(sols: &SolutionMap, format: Format, out: W) -> io::Result<()> {
+    match format {
+        Format::Text => write_text(sols, out),
+        Format::Json => write_json(sols, out),
+        Format::Csv => write_csv(sols, out),
+        Format::Dot => dot::write(sols, &DotOptions::default(), out),
+        Format::Tablebase => tablebase::write(sols, out),
+        Format::Packed => PackedTable::freeze(sols)?.write(out),
+    }
+}
+
+pub fn write_text<W: Write>(sols: &SolutionMap, mut out: W) -> io::Result<()> {
+    writeln!(out, "{}", sols)
+}
+
+pub fn write_json<W: Write>(sols: &SolutionMap, mut out: W) -> io::Result<()> {
+    writeln!(out, "{{")?;
+    writeln!(out, "  \"convention\": \"{:?}\",", sols.convention())?;
+    writeln!(out, "  \"limits\": {},", json_list(sols.board().limits()))?;
+    if let Some(sets) = sols.board().take_sets() {
+        let sets: Vec<String> = sets.iter().map(|set| json_list(set.takes())).collect();
+        writeln!(out, "  \"take_sets\": [{}],", sets.join(", "))?;
+    }
+    writeln!(out, "  \"states\": [")?;
+    let len = sols.board().len();
+    for (i, s) in sols.board().states().enumerate() {
+        let moves: Vec<String> = sols.winning_moves(&s)?
+            .iter()
+            .map(|mv| format!("{{\"heap\": {}, \"take\": {}}}", mv.heap + 1, mv.take))
+            .collect();
+        writeln!(out, "    {{\"heaps\": {}, \"conclusion\": \"{:?}\", \"nim_sum\": {}, \"grundy\": {}, \"depth\": {}, \"winning_moves\": [{}]}}{}",
+                 json_list(&s.0), sols.conclusion(&s)?, s.nim_sum(), sols.grundy(&s)?, sols.depth(&s)?, moves.join(", "),
+                 if i + 1 < len { "," } else { "" })?;
+    }
+    writeln!(out, "  ]")?;
+    writeln!(out, "}}")
+}
+
+pub fn write_csv<W: Write>(sols: &SolutionMap, mut out: W) -> io::Result<()> {
+    let heaps: Vec<String> = (1..=sols.board().limits().len()).map(|h| format!("heap{}", h)).collect();
+    writeln!(out, "{},conclusion,nim_sum,grundy,depth,winning_moves", heaps.join(","))?;
+    for s in sols.board().states() {
+        let heaps: Vec<String> = s.0.iter().map(|h| h.to_string()).collect();
+        let moves: Vec<String> = sols.winning_moves(&s)?.iter().map(Move::to_string).collect();
+        writeln!(out, "{},{:?},{},{},{},{}",
+                 heaps.join(","), sols.conclusion(&s)?, s.nim_sum(), sols.grundy(&s)?, sols.depth(&s)?, moves.join(";"))?;
+    }
+    Ok(())
+}
+
+fn json_list(values: &[u8]) -> String {
+    let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+    format!("[{}]", values.join(", "))
+}
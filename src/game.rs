@@ -0,0 +1,82 @@
+//! The rules a game needs for `SolutionMap` to solve it: two players alternate moves, no
+//! position can be repeated, and whoever can't move has won or lost by the game's rules.
+
+use std::fmt;
+
+use crate::board::Board;
+use crate::solution::{Conclusion, Convention, QueryError};
+use crate::state::State;
+
+/// A finite game whose positions are numbered densely from 0.
+///
+/// Every move must lead to a lower-numbered position. That rules out cycles, and lets values
+/// that depend only on a position's successors be computed in one pass in order of number.
+pub trait Game {
+    type Position: Clone + fmt::Debug + Eq;
+
+    /// Number of positions.
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of `p`, or why it isn't a position of this game.
+    fn index(&self, p: &Self::Position) -> Result<usize, QueryError>;
+
+    /// The position numbered `index`.
+    fn position(&self, index: usize) -> Self::Position;
+
+    /// Numbers of every position reachable from `index` in one move, each listed once.
+    fn successors(&self, index: usize) -> Vec<usize>;
+
+    /// Numbers of every position from which `index` can be reached in one move, each listed
+    /// once.
+    fn predecessors(&self, index: usize) -> Vec<usize>;
+
+    /// How a position without moves is judged by whoever left it.
+    fn terminal(&self, index: usize) -> Conclusion;
+}
+
+/// Nim on a board: take sticks from one heap, as many as the heap's take-set allows or any
+/// number without take-sets, until no move is left.
+#[derive(Clone)]
+pub struct Nim {
+    pub board: Board,
+    pub convention: Convention,
+}
+
+impl Nim {
+    pub fn new(board: &Board, convention: Convention) -> Self {
+        Nim { board: board.clone(), convention }
+    }
+}
+
+impl Game for Nim {
+    type Position = State;
+
+    fn len(&self) -> usize {
+        self.board.len()
+    }
+
+    fn index(&self, p: &State) -> Result<usize, QueryError> {
+        self.board.try_index(p)
+    }
+
+    fn position(&self, index: usize) -> State {
+        self.board.state(index)
+    }
+
+    fn successors(&self, index: usize) -> Vec<usize> {
+        self.board.children(index)
+    }
+
+    fn predecessors(&self, index: usize) -> Vec<usize> {
+        self.board.parents(index)
+    }
+
+    fn terminal(&self, _index: usize) -> Conclusion {
+        // Mark (0) as LOSING under misere play, WINNING under normal play
+        self.convention.terminal()
+    }
+}
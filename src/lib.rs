@@ -0,0 +1,26 @@
+//! Solver for Nim-like games: builds the table of who wins from every state of a board, and
+//! answers questions about it.
+
+pub mod audit;
+pub mod board;
+pub mod dot;
+pub mod explain;
+pub mod game;
+pub mod export;
+pub mod octal;
+pub mod packed;
+pub mod periodicity;
+pub mod play;
+pub mod search;
+pub mod solution;
+pub mod state;
+pub mod subtraction;
+pub mod tablebase;
+
+pub use board::{Board, BoardError};
+pub use game::{Game, Nim};
+pub use octal::{Heaps, OctalCode, OctalGame};
+pub use periodicity::Periodicity;
+pub use solution::{Conclusion, Convention, Justification, QueryError, SolutionMap};
+pub use state::{Move, MoveError, State};
+pub use subtraction::TakeSet;
@@ -1,146 +1,256 @@
-use std::cmp::Ordering;
-use std::collections::BTreeMap;
-use std::fmt;
+mod cli;
 
-#[derive(Debug, Eq, PartialEq)]
-enum Conclusion {
-    Winning,
-    Losing,
-    Unknown
-}
-
-#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, Ord, PartialOrd)]
-struct State(u8 /* top heap (1 or 0) */, u8 /* heap 2 */, u8 /* heap 3 */, u8 /* heap 4 */);
-
-struct SolutionMap(BTreeMap<State, Conclusion>);
-
-impl SolutionMap {
-    fn new() -> Self {
-        let mut states: BTreeMap<_, _> = Default::default();
-        // populate the states
-        for heap0 in 0..=1u8 {
-            for heap1 in 0..=3u8 {
-                for heap2 in 0..=5u8 {
-                    for heap3 in 0..=7u8 {
-                        states.insert(State(heap0, heap1, heap2, heap3), Conclusion::Unknown);
-                    }
-                }
-            }
-        }
-
-        // Mark (0) as a LOSING state
-        *states.get_mut(&State(0, 0, 0, 0)).unwrap() = Conclusion::Losing;
-
-        Self(states)
-    }
+use std::env;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::process;
 
-    fn mark(&mut self, s: State, v: Conclusion) {
-        *self.0.get_mut(&s).unwrap() = v;
-    }
-
-    fn is_losing(&self, s: State) -> bool {
-        *self.0.get(&s).unwrap() == Conclusion::Losing
-    }
+use nim::packed::PackedTable;
+use nim::search::{Disagreement, Search};
+use nim::{dot, explain, export, periodicity, play, search, Conclusion, Convention, Game, Heaps, OctalCode, OctalGame,
+          Periodicity, SolutionMap, State};
 
-    fn is_winning(&self, s: State) -> bool {
-        *self.0.get(&s).unwrap() == Conclusion::Winning
-    }
+use crate::cli::{Args, Command, Table};
 
-    fn find_winning_states(&self) -> Vec<State> {
-        self.0.iter()
-            .filter(|(_, v)| **v == Conclusion::Winning)
-            .map(|(&s, _)| s)
-            .collect()
+fn main() {
+    let args = cli::parse(env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("error: {}\n\n{}", e, cli::USAGE);
+        process::exit(2);
+    });
+    match run(&args) {
+        Ok(true) => {},
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            process::exit(1);
+        },
     }
+}
 
-    fn parents_of(&self, child: State) -> Vec<State> {
-        self.0.iter()
-            .filter(move |(&parent, _)| child.is_child_of(parent))
-            .map(|(&parent, _)| parent)
-            .collect()
+/// Carry out the command, returning whether it succeeded.
+fn run(args: &Args) -> Result<bool, Box<dyn Error>> {
+    if let Command::Help = args.command {
+        println!("{}", cli::USAGE);
+        return Ok(true);
     }
-
-    fn children_of(&self, parent: State) -> Vec<State> {
-        self.0.iter()
-            .filter(move |(&child, _)| child.is_child_of(parent))
-            .map(|(&child, _)| child)
-            .collect()
+    if let Some(code) = &args.octal {
+        return run_octal(args, code);
     }
-
-    fn unsolved(&self) -> Vec<State> {
-        self.0.iter()
-            .filter(|(_, v)| **v == Conclusion::Unknown)
-            .map(|(&s, _)| s)
-            .collect()
+    let sols = match args.table()? {
+        Table::Solved(sols) => sols,
+        Table::Packed(packed) => return run_packed(args, &packed),
+    };
+    if let Command::Play = args.command {
+        let stdin = io::stdin();
+        play::play(&sols, stdin.lock(), io::stdout())?;
+        return Ok(true);
     }
 
-    fn is_solved(&self) -> bool {
-        self.unsolved().len() == 0
+    let mut out = output(args)?;
+    let mut ok = true;
+    match &args.command {
+        Command::Solve => export::write_text(&sols, &mut out)?,
+        Command::Query(s) => {
+            writeln!(out, "{}", sols.entry(s)?)?;
+            for mv in sols.winning_moves(s)? {
+                writeln!(out, "  winning move: {}", mv)?;
+            }
+        },
+        Command::Explain { state, depth } => explain::explain(&sols, state, *depth, &mut out)?,
+        Command::Export(export::Format::Dot) => dot::write(&sols, &args.dot, &mut out)?,
+        Command::Export(format) => export::write(&sols, *format, &mut out)?,
+        Command::Grundy => {
+            let board = sols.board();
+            for heap in 0..board.limits().len() {
+                // Read each size of the heap off the table, with every other heap empty
+                let mut s = State(vec![0; board.limits().len()]);
+                let mut values = Vec::new();
+                for size in 0..=board.limits()[heap] {
+                    s.0[heap] = size;
+                    values.push(sols.grundy(&s)?);
+                }
+                let text: Vec<String> = values.iter().map(|g| g.to_string()).collect();
+                let pattern = match board.take_sets() {
+                    Some(sets) => {
+                        write!(out, "heap {}, taking {}:", heap + 1, sets[heap])?;
+                        sets[heap].periodicity(&values)
+                    },
+                    None => {
+                        write!(out, "heap {}:", heap + 1)?;
+                        // No bound on the number taken, so no theorem to prove a pattern by
+                        periodicity::find(&values, |_| None)
+                    },
+                };
+                writeln!(out, " {}", text.join(" "))?;
+                write_pattern(&mut out, pattern, values.len())?;
+            }
+        },
+        Command::Verify => {
+            let grundy = sols.grundy_mismatches();
+            for s in &grundy {
+                writeln!(out, "{:?}: grundy {} differs from {} predicted by its heaps",
+                         s, sols.grundy(s)?, sols.board().grundy_sum(s)?)?;
+            }
+            let bouton = sols.bouton_mismatches();
+            if sols.board().take_sets().is_some() {
+                writeln!(out, "Bouton's rule only covers plain Nim, so it isn't checked under take-sets")?;
+            }
+            for s in &bouton {
+                writeln!(out, "{:?}: solved as {:?} but Bouton's rule predicts {:?}",
+                         s, sols.conclusion(s)?, s.bouton(sols.convention()))?;
+            }
+            let violations = sols.audit();
+            for v in &violations {
+                writeln!(out, "{}", v)?;
+            }
+            let disagreements = search::disagreements(&sols);
+            for d in &disagreements {
+                writeln!(out, "{}", d)?;
+            }
+            writeln!(out, "{} states checked, {} Grundy and {} Bouton mismatches, {} inconsistent conclusions, \
+                           {} disagreements with search",
+                     sols.board().len(), grundy.len(), bouton.len(), violations.len(), disagreements.len())?;
+            ok = grundy.is_empty() && bouton.is_empty() && violations.is_empty() && disagreements.is_empty();
+        },
+        Command::CrossCheck => {
+            let (mut boards, mut states, mut disagreements) = (0, 0, 0);
+            for board in search::small_boards() {
+                for &convention in &[Convention::Misere, Convention::Normal] {
+                    let sols = SolutionMap::solved(&board, convention);
+                    for d in search::disagreements(&sols) {
+                        let sets: Vec<String> = board.take_sets()
+                            .unwrap_or_default()
+                            .iter()
+                            .map(|set| format!(" taking {}", set))
+                            .collect();
+                        writeln!(out, "{:?} play on {:?}{}{}: {}", convention, board.limits(),
+                                 if board.is_symmetric() { " (symmetric)" } else { "" }, sets.concat(), d)?;
+                        disagreements += 1;
+                    }
+                    boards += 1;
+                    states += board.len();
+                }
+            }
+            writeln!(out, "{} boards and {} states checked, {} disagreements", boards, states, disagreements)?;
+            ok = disagreements == 0;
+        },
+        Command::Play | Command::Help => unreachable!(),
     }
+    out.flush()?;
+    Ok(ok)
 }
 
-impl State {
-    fn is_child_of(&self, parent: Self) -> bool {
-        match (self.0.cmp(&parent.0), self.1.cmp(&parent.1), self.2.cmp(&parent.2), self.3.cmp(&parent.3)) {
-            // If removing N sticks from EXACTLY one heap leads from parent -> self,
-            // then self is a direct child of parent
-            (Ordering::Less, Ordering::Equal, Ordering::Equal, Ordering::Equal) => true,
-            (Ordering::Equal, Ordering::Less, Ordering::Equal, Ordering::Equal) => true,
-            (Ordering::Equal, Ordering::Equal, Ordering::Less, Ordering::Equal) => true,
-            (Ordering::Equal, Ordering::Equal, Ordering::Equal, Ordering::Less) => true,
-            _ => false,
-        }
-    }
-
-    fn parity(&self) -> u8 {
-        let parity_1 = parity_ones(&[self.0, self.1, self.2, self.3]);
-        let parity_2 = parity_ones(&[self.0 >> 1, self.1 >> 1, self.2 >> 1, self.3 >> 1]);
-        let parity_4 = parity_ones(&[self.0 >> 2, self.1 >> 2, self.2 >> 2, self.3 >> 2]);
-        parity_1 + parity_2 + parity_4
+/// Carry out the command on a packed table, which only knows who wins from each state.
+fn run_packed(args: &Args, packed: &PackedTable) -> Result<bool, Box<dyn Error>> {
+    let mut out = output(args)?;
+    let mut ok = true;
+    match &args.command {
+        Command::Query(s) => {
+            writeln!(out, "{:?}: {:?}", s, if packed.is_winning(s)? { Conclusion::Winning } else { Conclusion::Losing })?;
+            for mv in packed.board().legal_moves(s) {
+                if packed.is_winning(&s.apply(mv)?)? {
+                    writeln!(out, "  winning move: {}", mv)?;
+                }
+            }
+        },
+        Command::Verify => {
+            let violations = packed.audit();
+            for v in &violations {
+                writeln!(out, "{}", v)?;
+            }
+            let mut search = Search::new(packed.board(), packed.convention());
+            let mut disagreements = 0;
+            for s in packed.board().states() {
+                let table = if packed.is_winning(&s)? { Conclusion::Winning } else { Conclusion::Losing };
+                let found = search.conclusion(&s)?;
+                if table != found {
+                    writeln!(out, "{}", Disagreement { state: s, table, search: found })?;
+                    disagreements += 1;
+                }
+            }
+            writeln!(out, "{} states checked, {} inconsistent conclusions, {} disagreements with search",
+                     packed.board().len(), violations.len(), disagreements)?;
+            ok = violations.is_empty() && disagreements == 0;
+        },
+        _ => return Err("a packed table only answers query and verify".into()),
     }
+    out.flush()?;
+    Ok(ok)
 }
 
-impl fmt::Display for SolutionMap {
-    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        for (s, v) in &self.0 {
-            writeln!(f, "{:?}: {:?} (parity {})", s, v, s.parity())?;
+/// Carry out the command on an octal game rather than a Nim board.
+fn run_octal(args: &Args, code: &OctalCode) -> Result<bool, Box<dyn Error>> {
+    let mut out = output(args)?;
+    if let Command::Grundy = args.command {
+        // Heaps alone don't need the table of every position
+        let values = code.grundy_sequence(args.sticks as usize + 1);
+        let text: Vec<String> = values.iter().map(|g| g.to_string()).collect();
+        writeln!(out, "{}: {}", code, text.join(" "))?;
+        write_pattern(&mut out, code.periodicity(&values), values.len())?;
+        if values.len() <= args.sticks as usize {
+            writeln!(out, "  the value for size {} doesn't fit in a byte", values.len())?;
         }
-        Ok(())
+        out.flush()?;
+        return Ok(true);
     }
-}
-
-fn parity_ones(values: &[u8]) -> u8 {
-    let mut s = 0;
-    for v in values {
-        s += v & 1;
-    }
-    s % 2
-}
 
-fn main() {
-    let mut sols = SolutionMap::new();
-
-    while !sols.is_solved() {
-        // All states which lead to ONLY losing states must be winning states. i.e. if you leave
-        // the board in this state, you force your opponent into a losing state.
-        for state in sols.unsolved() {
-            if sols.children_of(state).into_iter().all(|s| sols.is_losing(s)) {
-                sols.mark(state, Conclusion::Winning);
+    let sols = SolutionMap::solved_game(OctalGame::new(code, args.convention.unwrap(), args.sticks));
+    let mut ok = true;
+    match &args.command {
+        Command::Solve => write!(out, "{}", sols)?,
+        Command::Query(s) => {
+            let p = Heaps::new(&s.0);
+            writeln!(out, "{:?}: {:?} (grundy {}, depth {})", p, sols.conclusion(&p)?, sols.grundy(&p)?, sols.depth(&p)?)?;
+            let game = sols.game();
+            for child in game.successors(game.index(&p)?) {
+                let child = game.position(child);
+                if sols.is_winning(&child)? {
+                    writeln!(out, "  winning move: leave {:?}", child)?;
+                }
             }
-        }
-
-        // Any state which leads to at least ONE winning state must be a losing state. i.f. if you
-        // leave the board in this state, your opponent MAY put it into a state where they win.
-        let winning = sols.find_winning_states();
-        for win in winning {
-            for parent in sols.parents_of(win) {
-                sols.mark(parent, Conclusion::Losing);
+        },
+        Command::Verify => {
+            let values = code.grundy_sequence(args.sticks as usize + 1);
+            let grundy = sols.grundy_mismatches();
+            for p in &grundy {
+                writeln!(out, "{:?}: grundy {} differs from {} predicted by its heaps", p, sols.grundy(p)?, p.nim_sum(&values))?;
             }
-        }
-        // print the evolution ?
-        // println!("{}", sols);
+            let violations = sols.audit();
+            for v in &violations {
+                writeln!(out, "{}", v)?;
+            }
+            let mut published = 0;
+            if let Some(known) = code.known_values() {
+                let values = code.grundy_sequence(known.len());
+                for (size, (&found, &known)) in values.iter().zip(&known).enumerate() {
+                    if found != known {
+                        writeln!(out, "Heaps({}): grundy {} but the published value is {}", size, found, known)?;
+                        published += 1;
+                    }
+                }
+            }
+            writeln!(out, "{} positions checked, {} Grundy mismatches, {} inconsistent conclusions, \
+                           {} differences from published values",
+                     sols.game().len(), grundy.len(), violations.len(), published)?;
+            ok = grundy.is_empty() && violations.is_empty() && published == 0;
+        },
+        _ => unreachable!(),
     }
+    out.flush()?;
+    Ok(ok)
+}
 
-    println!("{}", sols);
+fn output(args: &Args) -> io::Result<Box<dyn Write>> {
+    Ok(match &args.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(BufWriter::new(io::stdout())),
+    })
 }
 
+fn write_pattern<W: Write>(out: &mut W, pattern: Option<Periodicity>, len: usize) -> io::Result<()> {
+    match pattern {
+        Some(p) => writeln!(out, "  {}", p),
+        None => writeln!(out, "  no repeating pattern up to size {}", len - 1),
+    }
+}
@@ -0,0 +1,412 @@
+//! Octal games, where a move takes sticks from a heap and may then split what's left into two
+//! heaps. A game is written as an octal code "d0.d1d2d3...", where digit `dk` says what may be
+//! left after taking `k` sticks from a heap: bit 1 allows taking the whole heap, bit 2 leaving
+//! one heap, and bit 4 leaving two. Kayles is 0.77 and Dawson's Kayles 0.07.
+//!
+//! A code ending in "..." repeats its last digit forever, so that moves may take any number of
+//! sticks. Lasker's Nim, where a move either takes sticks from a heap or splits it in two, is
+//! 4.3... in this notation.
+//!
+//! Since heaps can split, a position is a multiset of heaps rather than a `State` on a board.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::game::Game;
+use crate::periodicity::{self, Periodicity};
+use crate::solution::{Conclusion, Convention, QueryError, SolutionMap};
+
+/// The rules of an octal game.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OctalCode {
+    // The digit for taking `k` sticks is at `k`, with no zeros at the end
+    digits: Vec<u8>,
+    // Whether the last digit also covers taking any larger number of sticks
+    repeat: bool,
+}
+
+/// The heaps of a position in an octal game, smallest first. Empty heaps are left out, since
+/// no move can be made on them.
+#[derive(Clone, Eq, Hash, PartialEq, Ord, PartialOrd)]
+pub struct Heaps(pub Vec<u8>);
+
+/// An octal game played under a convention, with every position of up to `sticks` sticks.
+pub struct OctalGame {
+    pub code: OctalCode,
+    pub convention: Convention,
+    pub sticks: u8,
+    // Positions in order of number: by total sticks, and then by falling number of heaps, since
+    // splitting a heap without taking sticks keeps the total but adds a heap
+    positions: Vec<Heaps>,
+    numbers: HashMap<Heaps, usize>,
+    parents: Vec<Vec<usize>>,
+}
+
+/// The first Grundy values of Kayles (0.77), before the sequence settles into a period of 12.
+const KAYLES: &[u8] = &[
+    0, 1, 2, 3, 1, 4, 3, 2, 1, 4, 2, 6, 4, 1, 2, 7, 1, 4, 3, 2, 1, 4, 6, 7,
+    4, 1, 2, 8, 5, 4, 7, 2, 1, 8, 6, 7, 4, 1, 2, 3, 1, 4, 7, 2, 1, 8, 2, 7,
+    4, 1, 2, 8, 1, 4, 7, 2, 1, 4, 2, 7, 4, 1, 2, 8, 1, 4, 7, 2, 1, 8, 6, 7,
+    4, 1, 2, 8, 1, 4, 7, 2, 1, 8, 2, 7,
+];
+
+/// The first Grundy values of Dawson's Chess (0.137), before the sequence settles into a
+/// period of 34.
+const DAWSONS_CHESS: &[u8] = &[
+    0, 1, 1, 2, 0, 3, 1, 1, 0, 3, 3, 2, 2, 4, 0, 5, 2, 2, 3, 3, 0, 1, 1, 3,
+    0, 2, 1, 1, 0, 4, 5, 2, 7, 4, 0, 1, 1, 2, 0, 3, 1, 1, 0, 3, 3, 2, 2, 4,
+    4, 5, 5, 2, 3, 3, 0, 1, 1, 3, 0, 2, 1, 1, 0, 4, 5, 3, 7, 4, 8, 1, 1, 2,
+];
+
+impl OctalCode {
+    /// Lasker's Nim: take any number of sticks from a heap, or split a heap into two.
+    pub fn lasker() -> Self {
+        OctalCode { digits: vec![4, 3], repeat: true }
+    }
+
+    /// What may be left after taking `take` sticks, as bits 1, 2 and 4.
+    pub fn digit(&self, take: usize) -> u8 {
+        match self.digits.get(take) {
+            Some(&d) => d,
+            None if self.repeat => *self.digits.last().unwrap(),
+            None => 0,
+        }
+    }
+
+    /// The most sticks a move takes, or `None` if a move may take any number.
+    pub fn largest_take(&self) -> Option<usize> {
+        if self.repeat { None } else { Some(self.digits.len() - 1) }
+    }
+
+    /// Every way a move can leave a lone heap of `size`, as the heaps left behind.
+    pub fn options(&self, size: u8) -> Vec<Vec<u8>> {
+        let mut options = Vec::new();
+        for take in 0..=self.largest_take().map_or(size as usize, |t| t.min(size as usize)) {
+            let digit = self.digit(take);
+            let rest = size - take as u8;
+            if digit & 1 != 0 && rest == 0 {
+                options.push(vec![]);
+            }
+            if digit & 2 != 0 && rest > 0 {
+                options.push(vec![rest]);
+            }
+            if digit & 4 != 0 {
+                options.extend((1..=rest / 2).map(|a| vec![a, rest - a]));
+            }
+        }
+        options
+    }
+
+    /// The Grundy value of a lone heap of each size from 0 up to `len - 1`. A move leaving two
+    /// heaps is worth the nim-sum of their values. When moves may take any number of sticks
+    /// the values can outgrow a byte, and then the sequence stops short.
+    pub fn grundy_sequence(&self, len: usize) -> Vec<u8> {
+        let mut values: Vec<u8> = Vec::with_capacity(len);
+        for size in 0..len {
+            let mut seen = [false; 256];
+            for option in self.options(size as u8) {
+                seen[option.iter().fold(0, |acc, &h| acc ^ values[h as usize]) as usize] = true;
+            }
+            match seen.iter().position(|&s| !s) {
+                Some(g) => values.push(g as u8),
+                None => break,
+            }
+        }
+        values
+    }
+
+    /// The number of Grundy values that prove `p`, by the periodicity theorem for octal games:
+    /// once g(n + period) = g(n) for every n from the preperiod up to twice the preperiod, plus
+    /// the period and the most sticks a move takes, it holds for every larger n too. The
+    /// theorem needs a most sticks taken, so it can't prove anything about a code that repeats.
+    pub fn values_to_prove(&self, p: &Periodicity) -> Option<usize> {
+        match self.largest_take() {
+            Some(take) if p.saltus == 0 => Some(2 * p.preperiod + 2 * p.period + take),
+            _ => None,
+        }
+    }
+
+    /// The shortest pattern in `values`, the Grundy values of a lone heap in this game.
+    pub fn periodicity(&self, values: &[u8]) -> Option<Periodicity> {
+        periodicity::find(values, |p| self.values_to_prove(p))
+    }
+
+    /// Published Grundy values of a lone heap for well-known games, starting from size 0.
+    pub fn known_values(&self) -> Option<Vec<u8>> {
+        match self.to_string().as_str() {
+            "0.77" => Some(KAYLES.to_vec()),
+            "0.137" => Some(DAWSONS_CHESS.to_vec()),
+            // Dawson's Kayles is Dawson's Chess with one more stick in every heap
+            "0.07" => Some([&[0], DAWSONS_CHESS].concat()),
+            // Lasker's formula swaps each pair of values after a multiple of 4: g(4k + 1) =
+            // 4k + 1, g(4k + 2) = 4k + 2, g(4k + 3) = 4k + 4 and g(4k + 4) = 4k + 3
+            "4.3..." => Some((0..255).map(|n| match n % 4 {
+                0 if n > 0 => n - 1,
+                3 => n + 1,
+                _ => n,
+            }).collect()),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for OctalCode {
+    type Err = String;
+
+    /// Parses codes like "0.77", "4.07", ".137" or "4.3...", or the names "kayles", "dawson"
+    /// and "lasker".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = match s {
+            "kayles" => "0.77",
+            "dawson" => "0.07",
+            "lasker" => "4.3...",
+            _ => s,
+        };
+        let (s, mut repeat) = match s.strip_suffix("...") {
+            Some(s) => (s, true),
+            None => (s, false),
+        };
+        let (whole, fraction) = s.split_once('.').ok_or("an octal code needs a point, e.g. 0.77")?;
+        let mut digits = Vec::new();
+        for c in whole.chars().chain(fraction.chars()) {
+            match c.to_digit(8) {
+                Some(d) => digits.push(d as u8),
+                None => return Err(format!("{:?} is not an octal digit", c)),
+            }
+        }
+        if whole.is_empty() {
+            digits.insert(0, 0);
+        } else if whole.len() > 1 {
+            return Err("only one digit may come before the point".to_string());
+        }
+        if digits[0] & 3 != 0 {
+            return Err("a move that takes nothing must split the heap, so the first digit is 0 or 4".to_string());
+        }
+        if repeat && fraction.is_empty() {
+            return Err("\"...\" must follow a digit after the point".to_string());
+        }
+        while digits.len() > 1 && digits.last() == Some(&0) {
+            // Repeating a 0 allows no more moves than stopping
+            repeat = false;
+            digits.pop();
+        }
+        if digits.len() > 256 {
+            return Err("a move can take at most 255 sticks".to_string());
+        }
+        if digits == [0] {
+            return Err("the code allows no moves".to_string());
+        }
+        Ok(OctalCode { digits, repeat })
+    }
+}
+
+impl fmt::Display for OctalCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}.", self.digits[0])?;
+        if self.digits.len() == 1 {
+            // Only splits without taking, e.g. 4.0
+            return write!(f, "0");
+        }
+        for d in &self.digits[1..] {
+            write!(f, "{}", d)?;
+        }
+        if self.repeat {
+            write!(f, "...")?;
+        }
+        Ok(())
+    }
+}
+
+impl Heaps {
+    /// The position with these heaps, in any order.
+    pub fn new(heaps: &[u8]) -> Self {
+        let mut heaps: Vec<u8> = heaps.iter().copied().filter(|&h| h > 0).collect();
+        heaps.sort_unstable();
+        Heaps(heaps)
+    }
+
+    pub fn sticks(&self) -> usize {
+        self.0.iter().map(|&h| h as usize).sum()
+    }
+
+    pub fn nim_sum(&self, values: &[u8]) -> u8 {
+        self.0.iter().fold(0, |acc, &h| acc ^ values[h as usize])
+    }
+}
+
+impl OctalGame {
+    pub fn new(code: &OctalCode, convention: Convention, sticks: u8) -> Self {
+        let mut positions = Vec::new();
+        for total in 0..=sticks {
+            let mut partitions = Vec::new();
+            partition(total, total, &mut Vec::new(), &mut partitions);
+            partitions.sort_by_key(|p: &Vec<u8>| Reverse(p.len()));
+            positions.extend(partitions.into_iter().map(|p| Heaps::new(&p)));
+        }
+        let numbers = positions.iter().cloned().enumerate().map(|(i, p)| (p, i)).collect();
+        let mut game = OctalGame {
+            code: code.clone(),
+            convention,
+            sticks,
+            parents: vec![Vec::new(); positions.len()],
+            positions,
+            numbers,
+        };
+        for parent in 0..game.positions.len() {
+            for child in game.successors(parent) {
+                game.parents[child].push(parent);
+            }
+        }
+        game
+    }
+}
+
+// Every way of writing `total` as a sum of heaps no larger than `largest`, largest first
+fn partition(total: u8, largest: u8, heaps: &mut Vec<u8>, out: &mut Vec<Vec<u8>>) {
+    if total == 0 {
+        out.push(heaps.clone());
+        return;
+    }
+    for h in (1..=largest.min(total)).rev() {
+        heaps.push(h);
+        partition(total - h, h, heaps, out);
+        heaps.pop();
+    }
+}
+
+impl Game for OctalGame {
+    type Position = Heaps;
+
+    fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    fn index(&self, p: &Heaps) -> Result<usize, QueryError> {
+        let p = Heaps::new(&p.0);
+        self.numbers.get(&p).copied().ok_or(QueryError::TooManySticks {
+            sticks: p.sticks(),
+            limit: self.sticks as usize,
+        })
+    }
+
+    fn position(&self, index: usize) -> Heaps {
+        self.positions[index].clone()
+    }
+
+    fn successors(&self, index: usize) -> Vec<usize> {
+        let heaps = &self.positions[index].0;
+        let mut children = Vec::new();
+        for (i, &h) in heaps.iter().enumerate() {
+            // Moves on equal heaps lead to the same positions
+            if i > 0 && heaps[i - 1] == h {
+                continue;
+            }
+            for option in self.code.options(h) {
+                let rest = [&heaps[..i], &heaps[i + 1..], &option].concat();
+                children.push(self.numbers[&Heaps::new(&rest)]);
+            }
+        }
+        children.sort_unstable();
+        children.dedup();
+        children
+    }
+
+    fn predecessors(&self, index: usize) -> Vec<usize> {
+        self.parents[index].clone()
+    }
+
+    fn terminal(&self, _index: usize) -> Conclusion {
+        self.convention.terminal()
+    }
+}
+
+impl SolutionMap<OctalGame> {
+    /// Positions whose Grundy value isn't the nim-sum of their heaps' values alone, which the
+    /// Sprague-Grundy theorem rules out.
+    pub fn grundy_mismatches(&self) -> Vec<Heaps> {
+        let values = self.game().code.grundy_sequence(self.game().sticks as usize + 1);
+        self.game().positions.iter()
+            .filter(|p| self.grundy(p).unwrap() != p.nim_sum(&values))
+            .cloned()
+            .collect()
+    }
+}
+
+impl fmt::Display for SolutionMap<OctalGame> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let game = self.game();
+        writeln!(f, "{:?} play of {}, up to {} sticks", game.convention, game.code, game.sticks)?;
+        for p in &game.positions {
+            writeln!(f, "{:?}: {:?} (grundy {}, depth {})",
+                     p, self.conclusion(p).unwrap(), self.grundy(p).unwrap(), self.depth(p).unwrap())?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Heaps {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let mut t = f.debug_tuple("Heaps");
+        for heap in &self.0 {
+            t.field(heap);
+        }
+        t.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code(s: &str) -> OctalCode {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn published_values() {
+        assert_eq!(code("0.77").grundy_sequence(KAYLES.len()), KAYLES);
+        assert_eq!(code("0.137").grundy_sequence(DAWSONS_CHESS.len()), DAWSONS_CHESS);
+        assert_eq!(code("0.07").grundy_sequence(DAWSONS_CHESS.len() + 1)[1..], *DAWSONS_CHESS);
+    }
+
+    #[test]
+    fn lasker_formula() {
+        let lasker = OctalCode::lasker();
+        let formula = lasker.known_values().unwrap();
+        assert_eq!(formula[..9], [0, 1, 2, 4, 3, 5, 6, 8, 7]);
+        assert_eq!(lasker.grundy_sequence(formula.len()), formula);
+    }
+
+    #[test]
+    fn kayles_period() {
+        let kayles = code("0.77");
+        let p = kayles.periodicity(&kayles.grundy_sequence(168)).unwrap();
+        assert_eq!((p.preperiod, p.period, p.saltus, p.proven), (71, 12, 0, true));
+        let p = kayles.periodicity(&kayles.grundy_sequence(167)).unwrap();
+        assert_eq!((p.preperiod, p.period, p.proven), (71, 12, false));
+    }
+
+    #[test]
+    fn lasker_never_proven() {
+        // Moves may take any number of sticks, so the periodicity theorem doesn't apply
+        let lasker = OctalCode::lasker();
+        let p = lasker.periodicity(&lasker.grundy_sequence(200)).unwrap();
+        assert_eq!((p.preperiod, p.period, p.saltus, p.proven), (1, 4, 4, false));
+    }
+
+    #[test]
+    fn solved_lone_heaps() {
+        for code in [code("0.77"), code("0.137"), OctalCode::lasker()] {
+            let values = code.known_values().unwrap();
+            for convention in [Convention::Normal, Convention::Misere] {
+                let sols = SolutionMap::solved_game(OctalGame::new(&code, convention, 12));
+                for size in 0..=12 {
+                    assert_eq!(sols.grundy(&Heaps::new(&[size])), Ok(values[size as usize]), "{} heap {}", code, size);
+                }
+                assert!(sols.grundy_mismatches().is_empty());
+            }
+        }
+    }
+}
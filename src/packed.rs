@@ -0,0 +1,158 @@
+//! Frozen tables holding one bit per state: set if the state is winning for whoever left it.
+//! Once solving is done this is all that's needed to play perfectly, at a 32nd of the size of
+//! a tablebase.
+//!
+//! The file is a tablebase header (see the `tablebase` module) with the magic `NIMBITS\0`,
+//! followed by the bits in state order, least significant bit first. A `PackedTable` is backed
+//! by the file's bytes as they are, so any buffer of them will do: a `Vec` read from disk, or a
+//! memory map for tables too large to read in.
+
+use std::io::{self, Write};
+
+use crate::audit::{self, Violation};
+use crate::board::Board;
+use crate::game::Nim;
+use crate::solution::{Conclusion, Convention, QueryError, SolutionMap};
+use crate::state::State;
+use crate::tablebase::{self, TablebaseError};
+
+/// The first bytes of every packed file.
+pub const MAGIC: &[u8; 8] = b"NIMBITS\0";
+
+pub struct PackedTable<B = Vec<u8>> {
+    board: Board,
+    convention: Convention,
+    // The whole file, header included
+    bytes: B,
+    // Where the bits start in `bytes`
+    offset: usize,
+}
+
+impl PackedTable {
+    /// Pack a solved table. Fails if any of its states are unsolved, or if the board has more
+    /// heaps than the header can hold.
+    pub fn freeze(sols: &SolutionMap) -> io::Result<Self> {
+        let mut bits = vec![0; sols.board().len().div_ceil(8)];
+        for (i, s) in sols.board().states().enumerate() {
+            match sols.conclusion(&s)? {
+                Conclusion::Winning => bits[i / 8] |= 1 << (i % 8),
+                Conclusion::Losing => {},
+                Conclusion::Unknown => {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, "only fully solved tables can be packed"));
+                },
+            }
+        }
+        let mut bytes = Vec::with_capacity(bits.len() + 64);
+        tablebase::write_header(MAGIC, sols.board(), sols.convention(), &bits, &mut bytes)?;
+        let offset = bytes.len();
+        bytes.extend_from_slice(&bits);
+        Ok(PackedTable { board: sols.board().clone(), convention: sols.convention(), bytes, offset })
+    }
+}
+
+impl<B: AsRef<[u8]>> PackedTable<B> {
+    /// Check the header and checksum of a file's contents, and use them as the table.
+    pub fn from_bytes(bytes: B) -> Result<Self, TablebaseError> {
+        let mut input = bytes.as_ref();
+        let (header, mut crc) = tablebase::read_header(MAGIC, &mut input)?;
+        if header.states != header.board.len() as u64 {
+            return Err(TablebaseError::Mismatch(format!(
+                "header promises {} states but heap limits {:?} make {}",
+                header.states, header.board.limits(), header.board.len())));
+        }
+        let len = header.board.len().div_ceil(8);
+        if input.len() < len {
+            return Err(TablebaseError::Truncated);
+        }
+        if input.len() > len {
+            return Err(TablebaseError::Invalid(format!("{} bytes after the table", input.len() - len)));
+        }
+        crc.update(input);
+        crc.check(header.checksum)?;
+
+        let offset = bytes.as_ref().len() - len;
+        Ok(PackedTable { board: header.board, convention: header.convention, bytes, offset })
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn convention(&self) -> Convention {
+        self.convention
+    }
+
+    pub fn is_winning(&self, s: &State) -> Result<bool, QueryError> {
+        let i = self.board.try_index(s)?;
+        Ok(self.bytes.as_ref()[self.offset + i / 8] & (1 << (i % 8)) != 0)
+    }
+
+    pub fn is_losing(&self, s: &State) -> Result<bool, QueryError> {
+        self.is_winning(s).map(|winning| !winning)
+    }
+
+    /// States whose bit disagrees with their children's.
+    pub fn audit(&self) -> Vec<Violation> {
+        let bits = &self.bytes.as_ref()[self.offset..];
+        audit::audit(&Nim::new(&self.board, self.convention), |i| match bits[i / 8] & (1 << (i % 8)) {
+            0 => Conclusion::Losing,
+            _ => Conclusion::Winning,
+        })
+    }
+
+    /// Save the table, in the form `from_bytes` reads back.
+    pub fn write<W: Write>(&self, mut out: W) -> io::Result<()> {
+        out.write_all(self.bytes.as_ref())
+    }
+
+    /// The file's bytes, header included.
+    pub fn into_bytes(self) -> B {
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frozen() -> (SolutionMap, Vec<u8>) {
+        let sols = SolutionMap::solved(&Board::new(&[1, 2, 3, 4]).unwrap(), Convention::Normal);
+        let bytes = PackedTable::freeze(&sols).unwrap().into_bytes();
+        (sols, bytes)
+    }
+
+    #[test]
+    fn round_trip() {
+        let (sols, bytes) = frozen();
+        let table = PackedTable::from_bytes(bytes).unwrap();
+        assert!(table.board() == sols.board());
+        assert_eq!(table.convention(), sols.convention());
+        for s in sols.board().states() {
+            assert_eq!(table.is_winning(&s), sols.is_winning(&s));
+        }
+        assert!(table.audit().is_empty());
+    }
+
+    #[test]
+    fn truncated() {
+        let (_, bytes) = frozen();
+        for len in 0..bytes.len() {
+            assert!(matches!(PackedTable::from_bytes(&bytes[..len]), Err(TablebaseError::Truncated)),
+                    "cut to {} bytes", len);
+        }
+    }
+
+    #[test]
+    fn checksum_mismatch() {
+        let (_, mut bytes) = frozen();
+        *bytes.last_mut().unwrap() ^= 1;
+        assert!(matches!(PackedTable::from_bytes(bytes), Err(TablebaseError::Checksum { .. })));
+    }
+
+    #[test]
+    fn trailing_bytes() {
+        let (_, mut bytes) = frozen();
+        bytes.push(0);
+        assert!(matches!(PackedTable::from_bytes(bytes), Err(TablebaseError::Invalid(_))));
+    }
+}
@@ -0,0 +1,93 @@
+//! Periodicity of a heap's Grundy values. In many games the values of a lone heap settle into
+//! a repeating pattern, possibly rising by a fixed amount each time round: after the first
+//! `preperiod` sizes, g(n + period) = g(n) + saltus. A finite run of values can only suggest
+//! that, but for some games a theorem says how far a pattern must hold before it holds forever.
+
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Periodicity {
+    /// Number of sizes before the pattern starts.
+    pub preperiod: usize,
+    pub period: usize,
+    /// How much the values rise each period; 0 for a sequence that simply repeats.
+    pub saltus: u8,
+    /// Number of values the pattern was found in.
+    pub checked: usize,
+    /// Whether enough values were checked for the game's periodicity theorem to prove the
+    /// pattern goes on forever, rather than only being observed so far.
+    pub proven: bool,
+}
+
+/// The shortest pattern in `values`, the Grundy values of heaps of size 0, 1, 2 and so on.
+///
+/// `needed` gives the number of values the game's periodicity theorem needs to prove a
+/// pattern, or `None` if no theorem applies. A pattern that isn't proven is only reported if
+/// it covers at least half the values and repeats at least twice, and even then may break
+/// later.
+pub fn find<F>(values: &[u8], needed: F) -> Option<Periodicity>
+    where F: Fn(&Periodicity) -> Option<usize> {
+    let mut observed = None;
+    for period in 1..values.len() {
+        // The last two values a period apart fix the saltus
+        let last = values.len() - 1;
+        let saltus = match values[last].checked_sub(values[last - period]) {
+            Some(saltus) => saltus,
+            None => continue,
+        };
+        // Walk back to the first size the pattern holds from
+        let mut preperiod = last - period + 1;
+        while preperiod > 0 && values[preperiod - 1].checked_add(saltus) == Some(values[preperiod - 1 + period]) {
+            preperiod -= 1;
+        }
+
+        let mut p = Periodicity { preperiod, period, saltus, checked: values.len(), proven: false };
+        p.proven = needed(&p).is_some_and(|n| values.len() >= n);
+        if p.proven {
+            return Some(p);
+        }
+        if observed.is_none() && values.len() >= (2 * preperiod).max(preperiod + 2 * period) {
+            observed = Some(p);
+        }
+    }
+    observed
+}
+
+impl fmt::Display for Periodicity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "preperiod {}, period {}, saltus {}", self.preperiod, self.period, self.saltus)?;
+        if self.proven {
+            write!(f, " (proven)")
+        } else {
+            write!(f, " (only observed up to size {})", self.checked - 1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn observed_pattern() {
+        let values = [3, 0, 1, 2, 0, 1, 2, 0, 1];
+        let p = find(&values, |_| None).unwrap();
+        assert_eq!((p.preperiod, p.period, p.saltus, p.checked, p.proven), (1, 3, 0, 9, false));
+        // Too short to show the pattern twice after the preperiod
+        assert_eq!(find(&values[..6], |_| None), None);
+    }
+
+    #[test]
+    fn rising_pattern() {
+        let values = [0, 1, 2, 3, 4, 5, 6, 7];
+        let p = find(&values, |_| None).unwrap();
+        assert_eq!((p.preperiod, p.period, p.saltus), (0, 1, 1));
+    }
+
+    #[test]
+    fn proven_once_enough_values() {
+        let values = [0, 1, 0, 1, 0, 1];
+        assert!(find(&values, |_| Some(6)).unwrap().proven);
+        assert!(!find(&values, |_| Some(7)).unwrap().proven);
+    }
+}
@@ -0,0 +1,214 @@
+use std::io::{self, BufRead, Write};
+
+use crate::{Move, MoveError, QueryError, SolutionMap, State};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Player {
+    Human,
+    Computer,
+}
+
+/// One move of a game, and the state it was played from.
+struct Turn {
+    player: Player,
+    before: State,
+    mv: Move,
+}
+
+impl Turn {
+    fn after(&self) -> State {
+        self.before.apply(self.mv).unwrap()
+    }
+}
+
+enum Command {
+    Take(Move),
+    Undo,
+    Resign,
+    Help,
+}
+
+const HELP: &str = "\
+Commands:
+  heap N take K   remove K sticks from heap N
+  undo            take back your last move
+  resign          give up the game
+  help            show this message";
+
+/// Play one game on the solved board against a human reading from `input`.
+pub fn play<R: BufRead, W: Write>(sols: &SolutionMap, mut input: R, mut output: W) -> io::Result<()> {
+    writeln!(output, "{:?} play: whoever makes the last move {}.", sols.convention(),
+             if sols.is_winning(&sols.board().empty_state())? { "wins" } else { "loses" })?;
+    writeln!(output, "{}", HELP)?;
+    if sols.board().legal_moves(&sols.board().full_state()).is_empty() {
+        writeln!(output, "There are no moves to play.")?;
+        return Ok(());
+    }
+
+    let mut to_move = loop {
+        match prompt(&mut input, &mut output, "Do you want to move first? [y/n] ")? {
+            None => return Ok(()),
+            Some(answer) => match answer.as_str() {
+                "y" | "yes" => break Player::Human,
+                "n" | "no" => break Player::Computer,
+                _ => {},
+            },
+        }
+    };
+
+    let mut state = sols.board().full_state();
+    let mut history: Vec<Turn> = Vec::new();
+    let mut resigned = false;
+    while !sols.board().legal_moves(&state).is_empty() {
+        let mv = match to_move {
+            Player::Computer => {
+                let mv = computer_move(sols, &state)?;
+                writeln!(output, "Computer plays {}.", mv)?;
+                mv
+            },
+            Player::Human => {
+                show(&state, &mut output)?;
+                let line = match prompt(&mut input, &mut output, "> ")? {
+                    Some(line) => line,
+                    None => {
+                        resigned = true;
+                        break;
+                    },
+                };
+                match parse_command(&line) {
+                    Ok(Command::Take(mv)) => {
+                        if let Err(e) = sols.board().apply(&state, mv) {
+                            writeln!(output, "You can't play {}: {}.", mv, e)?;
+                            continue;
+                        }
+                        mv
+                    },
+                    Ok(Command::Undo) => {
+                        // Roll back to just before the human's last move
+                        match history.iter().rposition(|t| t.player == Player::Human) {
+                            Some(last) => {
+                                state = history[last].before.clone();
+                                history.truncate(last);
+                            },
+                            None => writeln!(output, "There is nothing to undo.")?,
+                        }
+                        continue;
+                    },
+                    Ok(Command::Resign) => {
+                        resigned = true;
+                        break;
+                    },
+                    Ok(Command::Help) => {
+                        writeln!(output, "{}", HELP)?;
+                        continue;
+                    },
+                    Err(e) => {
+                        writeln!(output, "{}; type \"help\" for a list of commands.", e)?;
+                        continue;
+                    },
+                }
+            },
+        };
+        let turn = Turn { player: to_move, before: state, mv };
+        state = turn.after();
+        history.push(turn);
+        to_move = match to_move {
+            Player::Human => Player::Computer,
+            Player::Computer => Player::Human,
+        };
+    }
+
+    let winner = if resigned {
+        Player::Computer
+    } else {
+        // Whoever made the last move either won or lost by it, depending on the convention
+        let last = history.last().expect("a finished game has at least one move").player;
+        match (last, sols.is_winning(&state)?) {
+            (player, true) => player,
+            (Player::Human, false) => Player::Computer,
+            (Player::Computer, false) => Player::Human,
+        }
+    };
+    let resigned_at = if resigned { Some(&state) } else { None };
+    summarize(sols, &history, resigned_at, winner, &mut output)
+}
+
+fn prompt<R: BufRead, W: Write>(input: &mut R, output: &mut W, msg: &str) -> io::Result<Option<String>> {
+    write!(output, "{}", msg)?;
+    output.flush()?;
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line.trim().to_lowercase()))
+}
+
+fn parse_command(line: &str) -> Result<Command, MoveError> {
+    match line {
+        "undo" => Ok(Command::Undo),
+        "resign" => Ok(Command::Resign),
+        "help" => Ok(Command::Help),
+        _ => line.parse().map(Command::Take),
+    }
+}
+
+fn show<W: Write>(state: &State, output: &mut W) -> io::Result<()> {
+    for (heap, &sticks) in state.0.iter().enumerate() {
+        writeln!(output, "  heap {}: {}", heap + 1, "|".repeat(sticks as usize))?;
+    }
+    Ok(())
+}
+
+/// Move into a state the table marks as winning for whoever leaves it, as quickly as possible.
+/// Without such a move, hold out as long as possible and hope for a mistake.
+fn computer_move(sols: &SolutionMap, state: &State) -> Result<Move, QueryError> {
+    let mut options = Vec::new();
+    for mv in sols.board().legal_moves(state) {
+        let after = state.apply(mv).unwrap();
+        options.push((mv, sols.is_winning(&after)?, sols.depth(&after)?));
+    }
+    let fastest_win = options.iter().filter(|&&(_, winning, _)| winning).min_by_key(|&&(_, _, depth)| depth);
+    let slowest_loss = options.iter().max_by_key(|&&(_, _, depth)| depth);
+    Ok(fastest_win.or(slowest_loss).expect("the game is not over").0)
+}
+
+fn summarize<W: Write>(sols: &SolutionMap, history: &[Turn], resigned_at: Option<&State>, winner: Player,
+                       output: &mut W) -> io::Result<()> {
+    writeln!(output)?;
+    writeln!(output, "{}", match winner {
+        Player::Human => "You win!",
+        Player::Computer => "The computer wins.",
+    })?;
+    writeln!(output, "Moves:")?;
+    for (i, turn) in history.iter().enumerate() {
+        writeln!(output, "  {}. {:?}: {} ({:?} -> {:?})",
+                 i + 1, turn.player, turn.mv, turn.before, turn.after())?;
+    }
+
+    // The human held a won game if they could have left a winning state, but didn't
+    let mut blunder = None;
+    let mut held_won_game = false;
+    for (i, t) in history.iter().enumerate().filter(|(_, t)| t.player == Player::Human) {
+        if sols.is_winning(&t.after())? {
+            held_won_game = true;
+        } else if let (None, Some(&better)) = (blunder, sols.winning_moves(&t.before)?.first()) {
+            blunder = Some((i, better));
+        }
+    }
+    let resigned_win = match resigned_at {
+        Some(s) => sols.winning_moves(s)?.first().map(|&better| (s, better)),
+        None => None,
+    };
+    match (blunder, resigned_win) {
+        (Some((i, better)), _) => {
+            writeln!(output, "You first let a won game slip at move {}: {} from {:?} would have kept it.",
+                     i + 1, better, history[i].before)?;
+        },
+        (None, Some((s, better))) => {
+            writeln!(output, "You resigned a won game: {} from {:?} would have kept it.", better, s)?;
+        },
+        (None, None) if held_won_game => writeln!(output, "You never let a won game slip.")?,
+        (None, None) => writeln!(output, "You never had a winning position.")?,
+    }
+    Ok(())
+}
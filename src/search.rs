@@ -0,0 +1,131 @@
+//! A second, independent solver: top-down negamax search from a single state, remembering each
+//! position it settles. It answers one query without building a table, and serves as a
+//! cross-check on the retrograde solver in `SolutionMap`.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::board::Board;
+use crate::solution::{Conclusion, Convention, QueryError, SolutionMap};
+use crate::state::State;
+use crate::subtraction::TakeSet;
+
+pub struct Search {
+    // Only the board's rules matter, not its limits
+    board: Board,
+    convention: Convention,
+    // Whether the player to move wins, for each position searched so far. Positions are
+    // stored with heaps sharing a take-set sorted, since then their order doesn't matter.
+    transpositions: HashMap<Vec<u8>, bool>,
+}
+
+/// A state the two solvers disagree about.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Disagreement {
+    pub state: State,
+    /// The conclusion in the table.
+    pub table: Conclusion,
+    /// The conclusion found by searching.
+    pub search: Conclusion,
+}
+
+impl Search {
+    /// A search playing by the rules of `board`.
+    pub fn new(board: &Board, convention: Convention) -> Self {
+        Search { board: board.clone(), convention, transpositions: HashMap::new() }
+    }
+
+    /// Who wins from `s`, seen by the player who just moved into it, as in `SolutionMap`. Heaps
+    /// may hold any number of sticks, but there must be as many as the board has.
+    pub fn conclusion(&mut self, s: &State) -> Result<Conclusion, QueryError> {
+        let heaps = self.board.limits().len();
+        if s.0.len() != heaps {
+            return Err(QueryError::HeapCount { expected: heaps, found: s.0.len() });
+        }
+        Ok(if self.mover_wins(self.key(s)) { Conclusion::Losing } else { Conclusion::Winning })
+    }
+
+    fn key(&self, s: &State) -> State {
+        self.board.sorted(s)
+    }
+
+    fn mover_wins(&mut self, s: State) -> bool {
+        if let Some(&wins) = self.transpositions.get(&s.0) {
+            return wins;
+        }
+        let moves = self.board.legal_moves(&s);
+        let wins = if moves.is_empty() {
+            // Whoever left the empty board decided the game
+            self.convention.terminal() == Conclusion::Losing
+        } else {
+            // Stop at the first move that leaves the opponent lost
+            moves.into_iter().any(|mv| {
+                let child = self.key(&s.apply(mv).unwrap());
+                !self.mover_wins(child)
+            })
+        };
+        self.transpositions.insert(s.0, wins);
+        wins
+    }
+}
+
+/// Every state where searching disagrees with the table.
+pub fn disagreements(sols: &SolutionMap) -> Vec<Disagreement> {
+    let mut search = Search::new(sols.board(), sols.convention());
+    sols.board().states()
+        .filter_map(|s| {
+            let table = sols.conclusion(&s).unwrap();
+            let found = search.conclusion(&s).unwrap();
+            if table == found { None } else { Some(Disagreement { state: s, table, search: found }) }
+        })
+        .collect()
+}
+
+/// A spread of small boards for comparing the solvers: every board of up to three heaps of up
+/// to four sticks, in any order, and every board of four heaps in ascending order, each with
+/// and without symmetry. Each is also played with the take-sets {1, 2} and {1, 3, 4} on every
+/// heap, and with those two and {2, 3} taking turns from heap to heap.
+pub fn small_boards() -> Vec<Board> {
+    let mut shapes: Vec<Vec<u8>> = vec![vec![]];
+    let mut limits = Vec::new();
+    for heaps in 1..=4 {
+        shapes = shapes.iter()
+            .flat_map(|shape| (0..=4).map(move |l| [shape.as_slice(), &[l]].concat()))
+            .collect();
+        limits.extend(shapes.iter().filter(|l| heaps < 4 || l.windows(2).all(|w| w[0] <= w[1])).cloned());
+    }
+    let sets = [TakeSet::new(&[1, 2]).unwrap(), TakeSet::new(&[1, 3, 4]).unwrap(), TakeSet::new(&[2, 3]).unwrap()];
+    let mut boards = Vec::new();
+    for l in &limits {
+        for board in [Board::new(l).unwrap(), Board::symmetric(l).unwrap()] {
+            boards.push(board.with_take_sets(vec![sets[0].clone(); l.len()]).unwrap());
+            boards.push(board.with_take_sets(vec![sets[1].clone(); l.len()]).unwrap());
+            boards.push(board.with_take_sets((0..l.len()).map(|h| sets[h % 3].clone()).collect()).unwrap());
+            boards.push(board);
+        }
+    }
+    boards
+}
+
+impl fmt::Display for Disagreement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{:?}: the table says {:?} but searching finds {:?}", self.state, self.table, self.search)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn agrees_with_solver() {
+        for board in &small_boards() {
+            for convention in [Convention::Normal, Convention::Misere] {
+                let sols = SolutionMap::solved(board, convention);
+                if let Some(d) = disagreements(&sols).first() {
+                    panic!("{:?} under {:?}: {}", board.limits(), convention, d);
+                }
+            }
+        }
+    }
+}
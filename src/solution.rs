@@ -0,0 +1,403 @@
+use std::collections::VecDeque;
+use std::convert::TryInto;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+use crate::audit::{self, Violation};
+use crate::board::Board;
+use crate::game::{Game, Nim};
+use crate::state::{Move, State};
+
+/// Who wins from a state, seen by the player who just moved into it. A WINNING state is one
+/// you want to leave your opponent in.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Conclusion {
+    Winning,
+    Losing,
+    Unknown
+}
+
+/// Who wins when the last move is made. Without take-sets that move takes the last stick.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Convention {
+    /// Whoever makes the last move loses.
+    Misere,
+    /// Whoever makes the last move wins.
+    Normal,
+}
+
+/// Why a table can't answer a question about a state.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum QueryError {
+    /// The state has a different number of heaps from the board.
+    HeapCount { expected: usize, found: usize },
+    /// A heap holds more sticks than the board allows.
+    OutOfRange { heap: usize, sticks: u8, limit: u8 },
+    /// The position holds more sticks in all than the table goes up to.
+    TooManySticks { sticks: usize, limit: usize },
+    /// The position is in the game, but the table hasn't concluded who wins from it, or hasn't
+    /// computed its Grundy values yet. This holds the position as it is printed.
+    Unsolved(String),
+}
+
+/// The conclusion and Grundy value of every position of a game, by default every state of a
+/// Nim board.
+pub struct SolutionMap<G: Game = Nim> {
+    game: G,
+    conclusions: Vec<Conclusion>,
+    // Sprague-Grundy value of each state. These describe normal play whatever the convention.
+    grundy: Vec<u8>,
+    // Whether `solve_grundy` has filled in `grundy`
+    grundy_solved: bool,
+    // Moves left in each state if the winner hurries and the loser stalls, for concluded states
+    depth: Vec<u16>,
+}
+
+/// Why a solved state has its conclusion, as returned by `SolutionMap::justification`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Justification {
+    /// The game is over, so the convention decides.
+    GameOver,
+    /// The state is losing because this move leaves a winning state.
+    WinningMove(Move),
+    /// The state is winning because every move leaves a losing state.
+    EveryMoveLoses,
+    /// The state is marked losing, but no move leaves a winning state. `audit` says more.
+    Unproven,
+}
+
+impl Convention {
+    /// How the empty board should be judged by whoever left it.
+    pub fn terminal(self) -> Conclusion {
+        match self {
+            Convention::Misere => Conclusion::Losing,
+            Convention::Normal => Conclusion::Winning,
+        }
+    }
+}
+
+impl FromStr for Convention {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "misere" => Ok(Convention::Misere),
+            "normal" => Ok(Convention::Normal),
+            _ => Err(format!("unknown play convention {:?}", s)),
+        }
+    }
+}
+
+impl SolutionMap {
+    /// A table for `board` where nothing is concluded yet.
+    pub fn new(board: &Board, convention: Convention) -> Self {
+        Self::from_game(Nim::new(board, convention))
+    }
+
+    pub(crate) fn from_parts(board: Board, convention: Convention, conclusions: Vec<Conclusion>,
+                             grundy: Vec<u8>, depth: Vec<u16>) -> Self {
+        Self { game: Nim { board, convention }, conclusions, grundy, grundy_solved: true, depth }
+    }
+
+    /// A table for `board`, with every state solved.
+    pub fn solved(board: &Board, convention: Convention) -> Self {
+        Self::solved_game(Nim::new(board, convention))
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.game.board
+    }
+
+    pub fn convention(&self) -> Convention {
+        self.game.convention
+    }
+
+    /// Why `s` has its conclusion. A losing state is justified by its quickest winning move,
+    /// the one `solve` found first.
+    pub fn justification(&self, s: &State) -> Result<Justification, QueryError> {
+        let moves = self.board().legal_moves(s);
+        match self.solved_conclusion(s)? {
+            _ if moves.is_empty() => Ok(Justification::GameOver),
+            Conclusion::Winning => Ok(Justification::EveryMoveLoses),
+            _ => {
+                let child = |mv: &Move| self.game.board.index(&s.apply(*mv).unwrap());
+                let proof = moves.into_iter()
+                    .filter(|mv| self.conclusions[child(mv)] == Conclusion::Winning)
+                    .min_by_key(|mv| self.depth[child(mv)]);
+                Ok(proof.map_or(Justification::Unproven, Justification::WinningMove))
+            },
+        }
+    }
+
+    /// The line describing `s` in the table's listing.
+    pub fn entry<'a>(&'a self, s: &'a State) -> Result<Entry<'a>, QueryError> {
+        let index = self.game.board.try_index(s)?;
+        Ok(Entry { sols: self, state: s, index })
+    }
+
+    /// Every move from `s` that leaves a winning state.
+    pub fn winning_moves(&self, s: &State) -> Result<Vec<Move>, QueryError> {
+        self.game.board.try_index(s)?;
+        Ok(self.board().legal_moves(s)
+            .into_iter()
+            .filter(|&mv| self.conclusions[self.game.board.index(&s.apply(mv).unwrap())] == Conclusion::Winning)
+            .collect())
+    }
+
+    /// States whose solved conclusion differs from the one Bouton's theorem predicts.
+    /// Bouton's theorem only covers plain Nim, so this is always empty under take-sets.
+    pub fn bouton_mismatches(&self) -> Vec<State> {
+        if self.game.board.take_sets().is_some() {
+            return vec![];
+        }
+        self.game.board.states()
+            .zip(&self.conclusions)
+            .filter(|(s, &v)| s.bouton(self.game.convention) != v)
+            .map(|(s, _)| s)
+            .collect()
+    }
+
+    /// States whose Grundy value differs from the one the Sprague-Grundy theorem predicts
+    /// from their heaps, which for plain Nim is their nim-sum.
+    pub fn grundy_mismatches(&self) -> Vec<State> {
+        self.game.board.states()
+            .zip(&self.grundy)
+            .filter(|(s, &g)| Ok(g) != self.game.board.grundy_sum(s))
+            .map(|(s, _)| s)
+            .collect()
+    }
+}
+
+impl<G: Game> SolutionMap<G> {
+    /// A table for `game` where nothing is concluded yet.
+    pub fn from_game(game: G) -> Self {
+        let len = game.len();
+        Self {
+            game,
+            conclusions: vec![Conclusion::Unknown; len],
+            grundy: vec![0; len],
+            grundy_solved: false,
+            depth: vec![0; len],
+        }
+    }
+
+    /// A table for `game`, with every position solved.
+    pub fn solved_game(game: G) -> Self {
+        let mut sols = Self::from_game(game);
+        sols.solve();
+        sols.solve_grundy();
+        sols
+    }
+
+    pub fn game(&self) -> &G {
+        &self.game
+    }
+
+    pub fn mark(&mut self, p: &G::Position, v: Conclusion) -> Result<(), QueryError> {
+        let i = self.game.index(p)?;
+        self.conclusions[i] = v;
+        Ok(())
+    }
+
+    pub fn is_losing(&self, p: &G::Position) -> Result<bool, QueryError> {
+        self.solved_conclusion(p).map(|v| v == Conclusion::Losing)
+    }
+
+    pub fn is_winning(&self, p: &G::Position) -> Result<bool, QueryError> {
+        self.solved_conclusion(p).map(|v| v == Conclusion::Winning)
+    }
+
+    fn solved_conclusion(&self, p: &G::Position) -> Result<Conclusion, QueryError> {
+        match self.conclusion(p)? {
+            Conclusion::Unknown => Err(QueryError::Unsolved(format!("{:?}", p))),
+            v => Ok(v),
+        }
+    }
+
+    /// Retrograde analysis: work backwards from the concluded states, resolving each state
+    /// exactly once. A state becomes LOSING as soon as one child is found WINNING, and WINNING
+    /// once its last child is found LOSING. Unconcluded states without children are judged by
+    /// the game's terminal rules before anything else.
+    ///
+    /// States are resolved in order of depth, so the first WINNING child found is the quickest
+    /// win, and the last LOSING child found is the slowest loss.
+    pub fn solve(&mut self) {
+        // Number of children of each unsolved state not yet known to be losing. Two bytes
+        // keep this small next to the table itself.
+        let mut pending: Vec<u16> = vec![0; self.game.len()];
+        let mut queue = VecDeque::new();
+        for (i, pending) in pending.iter_mut().enumerate() {
+            if self.conclusions[i] == Conclusion::Unknown {
+                *pending = self.game.successors(i).len().try_into().expect("more than 65535 moves from one position");
+                if *pending == 0 {
+                    // e.g. Mark (0) as LOSING under misere play, WINNING under normal play
+                    self.conclusions[i] = self.game.terminal(i);
+                }
+            }
+            if self.conclusions[i] != Conclusion::Unknown {
+                queue.push_back(i);
+            }
+        }
+
+        while let Some(child) = queue.pop_front() {
+            for parent in self.game.predecessors(child) {
+                if self.conclusions[parent] != Conclusion::Unknown {
+                    continue;
+                }
+                if self.conclusions[child] == Conclusion::Winning {
+                    // Any state which leads to at least ONE winning state must be a losing
+                    // state. i.e. if you leave the board in this state, your opponent MAY put
+                    // it into a state where they win.
+                    self.conclusions[parent] = Conclusion::Losing;
+                    self.depth[parent] = self.depth[child] + 1;
+                    queue.push_back(parent);
+                } else if self.conclusions[child] == Conclusion::Losing {
+                    pending[parent] -= 1;
+                    if pending[parent] == 0 {
+                        // All states which lead to ONLY losing states must be winning states.
+                        // i.e. if you leave the board in this state, you force your opponent
+                        // into a losing state.
+                        self.conclusions[parent] = Conclusion::Winning;
+                        self.depth[parent] = self.depth[child] + 1;
+                        queue.push_back(parent);
+                    }
+                }
+            }
+        }
+    }
+
+
+    /// The conclusion for `p`, which is `Unknown` if it hasn't been solved.
+    pub fn conclusion(&self, p: &G::Position) -> Result<Conclusion, QueryError> {
+        Ok(self.conclusions[self.game.index(p)?])
+    }
+
+    /// Number of moves left from `p` when the player who can win finishes as fast as possible,
+    /// and the other player holds out as long as possible.
+    pub fn depth(&self, p: &G::Position) -> Result<u16, QueryError> {
+        self.solved_conclusion(p)?;
+        Ok(self.depth[self.game.index(p)?])
+    }
+
+    /// The Grundy value of `p`, once `solve_grundy` has run.
+    pub fn grundy(&self, p: &G::Position) -> Result<u8, QueryError> {
+        let i = self.game.index(p)?;
+        if !self.grundy_solved {
+            return Err(QueryError::Unsolved(format!("{:?}", p)));
+        }
+        Ok(self.grundy[i])
+    }
+
+    /// Assign each state the mex (minimum excluded value) of its children's Grundy values.
+    pub fn solve_grundy(&mut self) {
+        // Every child has a smaller index than its parent, so one forward pass suffices
+        for parent in 0..self.game.len() {
+            let children = self.game.successors(parent);
+            self.grundy[parent] = mex(children.into_iter().map(|c| self.grundy[c]));
+        }
+        self.grundy_solved = true;
+    }
+
+    /// States whose conclusion disagrees with their children's, which a correct solver never
+    /// leaves behind.
+    pub fn audit(&self) -> Vec<Violation<G::Position>> {
+        audit::audit(&self.game, |i| self.conclusions[i])
+    }
+}
+
+impl fmt::Display for SolutionMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{:?} play", self.game.convention)?;
+        if self.game.board.is_symmetric() {
+            write!(f, ", up to permutations of equal heaps")?;
+        }
+        match self.game.board.take_sets() {
+            Some(sets) if sets.iter().all(|set| *set == sets[0]) => {
+                write!(f, ", taking {} sticks at a time", sets[0])?;
+            },
+            Some(sets) => {
+                let sets: Vec<String> = sets.iter()
+                    .enumerate()
+                    .map(|(heap, set)| format!("{} from heap {}", set, heap + 1))
+                    .collect();
+                write!(f, ", taking {}", sets.join(", "))?;
+            },
+            None => {},
+        }
+        writeln!(f)?;
+        for (index, s) in self.game.board.states().enumerate() {
+            writeln!(f, "{}", Entry { sols: self, state: &s, index })?;
+        }
+        Ok(())
+    }
+}
+
+/// One line of a table's listing, as returned by `SolutionMap::entry`.
+pub struct Entry<'a> {
+    sols: &'a SolutionMap,
+    state: &'a State,
+    index: usize,
+}
+
+impl fmt::Display for Entry<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let (s, i) = (self.state, self.index);
+        write!(f, "{:?}: {:?} (nim-sum {}, grundy {}, depth {})",
+               s, self.sols.conclusions[i], s.nim_sum(), self.sols.grundy[i], self.sols.depth[i])
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            QueryError::HeapCount { expected, found } => {
+                write!(f, "the state has {} heaps but the board has {}", found, expected)
+            },
+            QueryError::OutOfRange { heap, sticks, limit } => {
+                write!(f, "heap {} holds {} sticks but the board allows at most {}", heap + 1, sticks, limit)
+            },
+            QueryError::TooManySticks { sticks, limit } => {
+                write!(f, "the position holds {} sticks but the table stops at {}", sticks, limit)
+            },
+            QueryError::Unsolved(p) => write!(f, "{} hasn't been solved", p),
+        }
+    }
+}
+
+impl Error for QueryError {}
+
+impl From<QueryError> for io::Error {
+    fn from(e: QueryError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidInput, e)
+    }
+}
+
+fn mex(values: impl IntoIterator<Item=u8>) -> u8 {
+    let mut seen = [false; 256];
+    for v in values {
+        seen[v as usize] = true;
+    }
+    seen.iter().position(|&s| !s).expect("Grundy value exceeds 255") as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unsolved_queries() {
+        let mut sols = SolutionMap::new(&Board::new(&[1, 2, 3]).unwrap(), Convention::Misere);
+        let full = State(vec![1, 2, 3]);
+        assert!(matches!(sols.is_winning(&full), Err(QueryError::Unsolved(_))));
+        assert!(matches!(sols.depth(&full), Err(QueryError::Unsolved(_))));
+        assert!(matches!(sols.grundy(&full), Err(QueryError::Unsolved(_))));
+
+        sols.solve();
+        assert!(sols.depth(&full).is_ok());
+        assert!(matches!(sols.grundy(&full), Err(QueryError::Unsolved(_))));
+        sols.solve_grundy();
+        assert_eq!(sols.grundy(&full), Ok(0));
+    }
+}
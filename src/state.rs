@@ -0,0 +1,183 @@
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+use crate::solution::{Conclusion, Convention};
+
+/// Number of sticks in each heap, top heap first.
+#[derive(Clone, Eq, Hash, PartialEq, Ord, PartialOrd)]
+pub struct State(pub Vec<u8>);
+
+/// Removal of `take` sticks from a single heap. Heaps are numbered from 0 here, but from 1
+/// whenever a move is shown to or read from a person.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Move {
+    pub heap: usize,
+    pub take: u8,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum MoveError {
+    /// A move named a heap the state doesn't have.
+    NoSuchHeap(usize),
+    /// A move must take at least one stick.
+    TakeNothing,
+    /// A move tried to take more sticks than its heap holds.
+    TooFewSticks { heap: usize, has: u8, take: u8 },
+    /// Two states have different numbers of heaps, so no move joins them.
+    HeapCount,
+    /// Two states differ by something other than sticks taken from exactly one heap.
+    NotOneMove,
+    /// The board's rules don't allow taking that many sticks from the heap.
+    NotInTakeSet { heap: usize, take: u8 },
+    /// Text that doesn't read as "heap N take K".
+    Syntax(String),
+}
+
+impl State {
+    pub fn is_child_of(&self, parent: &Self) -> bool {
+        State::move_between(parent, self).is_ok()
+    }
+
+    /// Every move that takes one or more sticks from a single heap.
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        for (heap, &sticks) in self.0.iter().enumerate() {
+            for take in 1..=sticks {
+                moves.push(Move { heap, take });
+            }
+        }
+        moves
+    }
+
+    pub fn apply(&self, mv: Move) -> Result<State, MoveError> {
+        let has = *self.0.get(mv.heap).ok_or(MoveError::NoSuchHeap(mv.heap))?;
+        if mv.take == 0 {
+            return Err(MoveError::TakeNothing);
+        }
+        if mv.take > has {
+            return Err(MoveError::TooFewSticks { heap: mv.heap, has, take: mv.take });
+        }
+        let mut child = self.clone();
+        child.0[mv.heap] -= mv.take;
+        debug_assert!(child.is_child_of(self));
+        Ok(child)
+    }
+
+    /// The move leading from `parent` to `child`, if there is one.
+    pub fn move_between(parent: &State, child: &State) -> Result<Move, MoveError> {
+        if parent.0.len() != child.0.len() {
+            return Err(MoveError::HeapCount);
+        }
+        // If removing N sticks from EXACTLY one heap leads from parent -> child,
+        // then child is a direct child of parent
+        let mut mv = None;
+        for (heap, (theirs, mine)) in parent.0.iter().zip(&child.0).enumerate() {
+            match mine.cmp(theirs) {
+                Ordering::Less if mv.is_none() => mv = Some(Move { heap, take: theirs - mine }),
+                Ordering::Equal => {},
+                _ => return Err(MoveError::NotOneMove),
+            }
+        }
+        mv.ok_or(MoveError::NotOneMove)
+    }
+
+    /// The same heaps, smallest first. Permuting heaps never changes who wins unless they have
+    /// different take-sets; see `Board::sorted` for that.
+    pub fn sorted(&self) -> State {
+        let mut heaps = self.0.clone();
+        heaps.sort_unstable();
+        State(heaps)
+    }
+
+    pub fn nim_sum(&self) -> u8 {
+        self.0.iter().fold(0, |acc, h| acc ^ h)
+    }
+
+    /// The conclusion Bouton's theorem predicts for whoever left this state. Under normal play
+    /// a state is WINNING exactly when its nim-sum is 0. Misere play is the same unless every
+    /// heap has at most one stick, when leaving an odd number of heaps wins instead.
+    pub fn bouton(&self, convention: Convention) -> Conclusion {
+        let wins = match convention {
+            Convention::Misere if self.0.iter().all(|&h| h <= 1) => {
+                self.0.iter().filter(|&&h| h == 1).count() % 2 == 1
+            },
+            _ => self.nim_sum() == 0,
+        };
+        if wins {
+            Conclusion::Winning
+        } else {
+            Conclusion::Losing
+        }
+    }
+}
+
+impl FromStr for State {
+    type Err = ParseIntError;
+
+    /// Parses comma-separated heap sizes, e.g. "1,2,3,0".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let heaps = s.split(',')
+            .map(|h| h.trim().parse())
+            .collect::<Result<Vec<u8>, _>>()?;
+        Ok(State(heaps))
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "heap {} take {}", self.heap + 1, self.take)
+    }
+}
+
+impl FromStr for Move {
+    type Err = MoveError;
+
+    /// Parses moves written like "heap 3 take 2".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let syntax = || MoveError::Syntax(s.to_string());
+        let words: Vec<&str> = s.split_whitespace().collect();
+        match words.as_slice() {
+            ["heap", heap, "take", take] => {
+                let heap: usize = heap.parse().map_err(|_| syntax())?;
+                Ok(Move {
+                    heap: heap.checked_sub(1).ok_or_else(syntax)?,
+                    take: take.parse().map_err(|_| syntax())?,
+                })
+            },
+            _ => Err(syntax()),
+        }
+    }
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            MoveError::NoSuchHeap(heap) => write!(f, "there is no heap {}", heap + 1),
+            MoveError::TakeNothing => write!(f, "you must take at least one stick"),
+            MoveError::TooFewSticks { heap, has, take } => {
+                write!(f, "heap {} has only {} sticks, not {}", heap + 1, has, take)
+            },
+            MoveError::HeapCount => write!(f, "the states have different numbers of heaps"),
+            MoveError::NotOneMove => write!(f, "the states don't differ by a single move"),
+            MoveError::NotInTakeSet { heap, take } => {
+                write!(f, "the rules don't allow taking {} sticks from heap {}", take, heap + 1)
+            },
+            MoveError::Syntax(text) => write!(f, "{:?} doesn't read as \"heap N take K\"", text),
+        }
+    }
+}
+
+impl Error for MoveError {}
+
+impl fmt::Debug for State {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let mut t = f.debug_tuple("State");
+        for heap in &self.0 {
+            t.field(heap);
+        }
+        t.finish()
+    }
+}
@@ -0,0 +1,97 @@
+//! Subtraction games, where a move may only take certain numbers of sticks from a heap.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::periodicity::{self, Periodicity};
+
+/// The numbers of sticks a move may take from a heap, e.g. {1, 2, 3}.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TakeSet(Vec<u8>);
+
+impl TakeSet {
+    pub fn new(takes: &[u8]) -> Result<Self, String> {
+        if takes.contains(&0) {
+            return Err("a move must take at least one stick".to_string());
+        }
+        if takes.is_empty() {
+            return Err("a take-set needs at least one number".to_string());
+        }
+        let mut takes = takes.to_vec();
+        takes.sort_unstable();
+        takes.dedup();
+        Ok(TakeSet(takes))
+    }
+
+    /// The numbers in the set, in ascending order.
+    pub fn takes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn allows(&self, take: u8) -> bool {
+        self.0.binary_search(&take).is_ok()
+    }
+
+    /// The Grundy value of a lone heap of each size from 0 up to `len - 1`.
+    pub fn grundy_sequence(&self, len: usize) -> Vec<u8> {
+        let mut values: Vec<u8> = Vec::with_capacity(len);
+        for size in 0..len {
+            let mut seen = [false; 256];
+            for &take in self.0.iter().take_while(|&&t| t as usize <= size) {
+                seen[values[size - take as usize] as usize] = true;
+            }
+            // A heap has at most 255 moves, so some value up to 255 is always free
+            values.push(seen.iter().position(|&s| !s).unwrap() as u8);
+        }
+        values
+    }
+
+    /// The number of Grundy values that prove `p`. Each value is the mex of the values up to
+    /// the set's largest number below it, so once the pattern holds for that many sizes in a
+    /// row it decides every later value the same way. Values here never exceed the set's size,
+    /// so they can't rise forever and no pattern with a saltus is ever proven.
+    pub fn values_to_prove(&self, p: &Periodicity) -> Option<usize> {
+        let largest = *self.0.last().unwrap() as usize;
+        if p.saltus == 0 { Some(p.preperiod + p.period + largest) } else { None }
+    }
+
+    /// The shortest pattern in `values`, the Grundy values of a lone heap under this set.
+    pub fn periodicity(&self, values: &[u8]) -> Option<Periodicity> {
+        periodicity::find(values, |p| self.values_to_prove(p))
+    }
+}
+
+impl FromStr for TakeSet {
+    type Err = String;
+
+    /// Parses comma-separated numbers of sticks, e.g. "1,3,4".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let takes = s.split(',')
+            .map(|t| t.trim().parse())
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|e| e.to_string())?;
+        Self::new(&takes)
+    }
+}
+
+impl fmt::Display for TakeSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let takes: Vec<String> = self.0.iter().map(|t| t.to_string()).collect();
+        write!(f, "{{{}}}", takes.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proves_period_after_enough_values() {
+        // {1, 3, 4} goes 0, 1, 0, 1, 2, 3, 2 and then repeats
+        let set = TakeSet::new(&[1, 3, 4]).unwrap();
+        let p = set.periodicity(&set.grundy_sequence(11)).unwrap();
+        assert_eq!((p.preperiod, p.period, p.saltus, p.proven), (0, 7, 0, true));
+        // One value short, and too few to have seen the pattern twice either
+        assert_eq!(set.periodicity(&set.grundy_sequence(10)), None);
+    }
+}
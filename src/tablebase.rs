@@ -0,0 +1,373 @@
+//! Solved tables saved to disk, so large boards only need solving once.
+//!
+//! All integers are little-endian. The file is a header:
+//!
+//! | bytes | field                                                     |
+//! |-------|-----------------------------------------------------------|
+//! | 8     | magic, `NIMTABLE`                                         |
+//! | 2     | format version: 2 with take-sets, otherwise 1             |
+//! | 1     | convention: 0 for misere, 1 for normal                    |
+//! | 1     | flags: bit 0 marks a symmetric board, bit 1 take-sets     |
+//! | 1     | number of heaps, N                                        |
+//! | N     | limit of each heap                                        |
+//! |       | with take-sets, for each heap: its size K, then K numbers |
+//! | 8     | number of states, S                                       |
+//! | 4     | CRC-32 of every other byte of the file                    |
+//!
+//! followed by S records of 4 bytes in state order: the conclusion (0 unknown, 1 winning,
+//! 2 losing), the Grundy value and the 2-byte depth.
+//!
+//! Version 1 files predate take-sets, so only bit 0 of their flags may be set. Boards without
+//! take-sets are still written as version 1, which older readers understand.
+
+use std::convert::TryInto;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use crate::board::Board;
+use crate::solution::{Conclusion, Convention, QueryError, SolutionMap};
+use crate::subtraction::TakeSet;
+
+const MAGIC: &[u8; 8] = b"NIMTABLE";
+// The newest version, the first to allow take-sets
+const VERSION: u16 = 2;
+const RECORD_LEN: usize = 4;
+
+#[derive(Debug)]
+pub enum TablebaseError {
+    Io(io::Error),
+    /// The file ended before the header or records did.
+    Truncated,
+    /// The file doesn't start with the tablebase magic.
+    BadMagic,
+    UnsupportedVersion(u16),
+    /// A header field or record holds a value no writer produces.
+    Invalid(String),
+    /// The file describes a different table than the one it was expected to hold.
+    Mismatch(String),
+    /// The contents don't add up to the stored checksum.
+    Checksum { stored: u32, computed: u32 },
+}
+
+/// The fields of a header, in file order.
+pub(crate) struct Header {
+    pub(crate) board: Board,
+    pub(crate) convention: Convention,
+    pub(crate) states: u64,
+    pub(crate) checksum: u32,
+}
+
+pub fn write<W: Write>(sols: &SolutionMap, mut out: W) -> io::Result<()> {
+    let mut records = Vec::with_capacity(sols.board().len() * RECORD_LEN);
+    for s in sols.board().states() {
+        records.push(match sols.conclusion(&s)? {
+            Conclusion::Unknown => 0,
+            Conclusion::Winning => 1,
+            Conclusion::Losing => 2,
+        });
+        records.push(sols.grundy(&s)?);
+        // Unknown states have no depth yet, and are stored with 0
+        let depth = match sols.depth(&s) {
+            Err(QueryError::Unsolved(_)) => 0,
+            depth => depth?,
+        };
+        records.extend_from_slice(&depth.to_le_bytes());
+    }
+    write_header(MAGIC, sols.board(), sols.convention(), &records, &mut out)?;
+    out.write_all(&records)
+}
+
+pub fn read<R: Read>(mut input: R) -> Result<SolutionMap, TablebaseError> {
+    let (header, mut crc) = read_header(MAGIC, &mut input)?;
+    if header.states != header.board.len() as u64 {
+        return Err(TablebaseError::Mismatch(format!(
+            "header promises {} states but heap limits {:?} make {}",
+            header.states, header.board.limits(), header.board.len())));
+    }
+
+    // Read incrementally rather than trusting the header with one huge allocation
+    let len = header.board.len().checked_mul(RECORD_LEN)
+        .ok_or_else(|| TablebaseError::Invalid(format!("heap limits {:?}", header.board.limits())))?;
+    let mut records = Vec::new();
+    input.by_ref().take(len as u64).read_to_end(&mut records)?;
+    if records.len() < len {
+        return Err(TablebaseError::Truncated);
+    }
+    let extra = io::copy(&mut input, &mut io::sink())?;
+    if extra > 0 {
+        return Err(TablebaseError::Invalid(format!("{} bytes after the table", extra)));
+    }
+    crc.update(&records);
+    crc.check(header.checksum)?;
+
+    let mut conclusions = Vec::with_capacity(header.board.len());
+    let mut grundy = Vec::with_capacity(header.board.len());
+    let mut depth = Vec::with_capacity(header.board.len());
+    for record in records.chunks(RECORD_LEN) {
+        conclusions.push(match record[0] {
+            0 => Conclusion::Unknown,
+            1 => Conclusion::Winning,
+            2 => Conclusion::Losing,
+            v => return Err(TablebaseError::Invalid(format!("conclusion {}", v))),
+        });
+        grundy.push(record[1]);
+        depth.push(u16::from_le_bytes([record[2], record[3]]));
+    }
+    Ok(SolutionMap::from_parts(header.board, header.convention, conclusions, grundy, depth))
+}
+
+pub(crate) fn write_header<W: Write>(magic: &[u8; 8], board: &Board, convention: Convention,
+                                     body: &[u8], out: &mut W) -> io::Result<()> {
+    let heaps: u8 = board.limits().len().try_into().map_err(|_| io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("a tablebase holds at most 255 heaps, not {}", board.limits().len())))?;
+    let mut fields = Vec::new();
+    let version = if board.take_sets().is_some() { VERSION } else { 1 };
+    fields.extend_from_slice(&version.to_le_bytes());
+    fields.push(match convention {
+        Convention::Misere => 0,
+        Convention::Normal => 1,
+    });
+    fields.push(board.is_symmetric() as u8 | (board.take_sets().is_some() as u8) << 1);
+    fields.push(heaps);
+    fields.extend_from_slice(board.limits());
+    for set in board.take_sets().unwrap_or_default() {
+        fields.push(set.takes().len() as u8);
+        fields.extend_from_slice(set.takes());
+    }
+    fields.extend_from_slice(&(board.len() as u64).to_le_bytes());
+
+    let mut crc = Crc32::new();
+    crc.update(magic);
+    crc.update(&fields);
+    crc.update(body);
+    out.write_all(magic)?;
+    out.write_all(&fields)?;
+    out.write_all(&crc.finish().to_le_bytes())
+}
+
+/// Reads and validates a header, returning it along with the checksum of its bytes so far,
+/// ready to be continued over the body.
+pub(crate) fn read_header<R: Read>(magic: &[u8; 8], input: &mut R) -> Result<(Header, Crc32), TablebaseError> {
+    let mut crc = Crc32::new();
+    let mut field = |input: &mut R, len: usize| -> Result<Vec<u8>, TablebaseError> {
+        let mut buf = vec![0; len];
+        read_exact(input, &mut buf)?;
+        crc.update(&buf);
+        Ok(buf)
+    };
+
+    if field(input, magic.len())? != magic {
+        return Err(TablebaseError::BadMagic);
+    }
+    let version = u16::from_le_bytes(field(input, 2)?.try_into().unwrap());
+    if version == 0 || version > VERSION {
+        return Err(TablebaseError::UnsupportedVersion(version));
+    }
+    let convention = match field(input, 1)?[0] {
+        0 => Convention::Misere,
+        1 => Convention::Normal,
+        v => return Err(TablebaseError::Invalid(format!("convention {}", v))),
+    };
+    let flags = field(input, 1)?[0];
+    if flags > if version == 1 { 1 } else { 3 } {
+        return Err(TablebaseError::Invalid(format!("flags {:#x}", flags)));
+    }
+    let heaps = field(input, 1)?[0] as usize;
+    let limits = field(input, heaps)?;
+    let invalid = |_| TablebaseError::Invalid(format!("heap limits {:?}", limits));
+    let mut board = if flags & 1 == 1 { Board::symmetric(&limits) } else { Board::new(&limits) }
+        .map_err(invalid)?;
+    if flags & 2 == 2 {
+        let mut sets = Vec::with_capacity(heaps);
+        for _ in 0..heaps {
+            let len = field(input, 1)?[0] as usize;
+            let takes = field(input, len)?;
+            let set = TakeSet::new(&takes).map_err(|_| TablebaseError::Invalid(format!("take-set {:?}", takes)))?;
+            sets.push(set);
+        }
+        board = board.with_take_sets(sets).map_err(invalid)?;
+    }
+    let states = u64::from_le_bytes(field(input, 8)?.try_into().unwrap());
+
+    let mut checksum = [0; 4];
+    read_exact(input, &mut checksum)?;
+    let header = Header { board, convention, states, checksum: u32::from_le_bytes(checksum) };
+    Ok((header, crc))
+}
+
+fn read_exact<R: Read>(input: &mut R, buf: &mut [u8]) -> Result<(), TablebaseError> {
+    input.read_exact(buf).map_err(|e| match e.kind() {
+        io::ErrorKind::UnexpectedEof => TablebaseError::Truncated,
+        _ => TablebaseError::Io(e),
+    })
+}
+
+// The CRC of each byte on its own, so that packed tables of billions of states check quickly
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg());
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// The CRC-32 used by zip and PNG.
+pub(crate) struct Crc32(u32);
+
+impl Crc32 {
+    pub(crate) fn new() -> Self {
+        Crc32(!0)
+    }
+
+    pub(crate) fn update(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = (self.0 >> 8) ^ CRC_TABLE[((self.0 ^ b as u32) & 0xff) as usize];
+        }
+    }
+
+    pub(crate) fn finish(&self) -> u32 {
+        !self.0
+    }
+
+    pub(crate) fn check(&self, stored: u32) -> Result<(), TablebaseError> {
+        match self.finish() {
+            computed if computed == stored => Ok(()),
+            computed => Err(TablebaseError::Checksum { stored, computed }),
+        }
+    }
+}
+
+impl fmt::Display for TablebaseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            TablebaseError::Io(e) => write!(f, "{}", e),
+            TablebaseError::Truncated => write!(f, "the tablebase file is truncated"),
+            TablebaseError::BadMagic => write!(f, "not a tablebase file"),
+            TablebaseError::UnsupportedVersion(v) => write!(f, "unsupported tablebase version {}", v),
+            TablebaseError::Invalid(what) => write!(f, "invalid {} in tablebase", what),
+            TablebaseError::Mismatch(why) => write!(f, "mismatched tablebase: {}", why),
+            TablebaseError::Checksum { stored, computed } => {
+                write!(f, "corrupt tablebase: checksum is {:08x} but contents give {:08x}", stored, computed)
+            },
+        }
+    }
+}
+
+impl Error for TablebaseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TablebaseError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for TablebaseError {
+    fn from(e: io::Error) -> Self {
+        TablebaseError::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solved() -> SolutionMap {
+        SolutionMap::solved(&Board::new(&[1, 2, 3]).unwrap(), Convention::Misere)
+    }
+
+    fn file(sols: &SolutionMap) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write(sols, &mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn round_trip() {
+        let sols = solved();
+        let loaded = read(file(&sols).as_slice()).unwrap();
+        assert!(loaded.board() == sols.board());
+        assert_eq!(loaded.convention(), sols.convention());
+        for s in sols.board().states() {
+            assert_eq!(loaded.conclusion(&s), sols.conclusion(&s));
+            assert_eq!(loaded.grundy(&s), sols.grundy(&s));
+            assert_eq!(loaded.depth(&s), sols.depth(&s));
+        }
+    }
+
+    #[test]
+    fn truncated() {
+        let bytes = file(&solved());
+        for len in 0..bytes.len() {
+            assert!(matches!(read(&bytes[..len]), Err(TablebaseError::Truncated)), "cut to {} bytes", len);
+        }
+    }
+
+    #[test]
+    fn bad_magic() {
+        let mut bytes = file(&solved());
+        bytes[0] = b'X';
+        assert!(matches!(read(bytes.as_slice()), Err(TablebaseError::BadMagic)));
+    }
+
+    #[test]
+    fn checksum_mismatch() {
+        let mut bytes = file(&solved());
+        // The last Grundy value, which any byte can stand for
+        let last = bytes.len() - 3;
+        bytes[last] ^= 1;
+        assert!(matches!(read(bytes.as_slice()), Err(TablebaseError::Checksum { .. })));
+    }
+
+    #[test]
+    fn versions() {
+        let board = Board::new(&[1, 2, 3]).unwrap().with_take_sets(vec![TakeSet::new(&[1, 2]).unwrap(); 3]).unwrap();
+        let sols = SolutionMap::solved(&board, Convention::Misere);
+        let bytes = file(&sols);
+        assert_eq!(bytes[8..10], 2u16.to_le_bytes());
+        assert!(read(bytes.as_slice()).unwrap().board() == sols.board());
+        assert_eq!(file(&solved())[8..10], 1u16.to_le_bytes());
+
+        // A version 1 header can't mark take-sets
+        let mut old = bytes.clone();
+        old[8..10].copy_from_slice(&1u16.to_le_bytes());
+        assert!(matches!(read(old.as_slice()), Err(TablebaseError::Invalid(_))));
+        let mut new = bytes;
+        new[8..10].copy_from_slice(&3u16.to_le_bytes());
+        assert!(matches!(read(new.as_slice()), Err(TablebaseError::UnsupportedVersion(3))));
+    }
+
+    #[test]
+    fn too_many_heaps() {
+        let sols = SolutionMap::new(&Board::symmetric(&[1; 300]).unwrap(), Convention::Misere);
+        let err = write(&sols, Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn large_symmetric_board() {
+        // 16 heaps of up to 15 sticks have 16^16 states in order, too many to count, but only
+        // C(31, 16) in any order
+        let board = Board::symmetric(&[15; 16]).unwrap();
+        let mut bytes = Vec::new();
+        write_header(MAGIC, &board, Convention::Misere, &[], &mut bytes).unwrap();
+        assert!(matches!(read(bytes.as_slice()), Err(TablebaseError::Truncated)));
+    }
+
+    #[test]
+    fn trailing_bytes() {
+        let mut bytes = file(&solved());
+        bytes.push(0);
+        assert!(matches!(read(bytes.as_slice()), Err(TablebaseError::Invalid(_))));
+    }
+}